@@ -2,8 +2,10 @@
 extern crate criterion;
 
 use {
-    criterion::Criterion,
-    golomb_set::UnpackedGcs,
+    byteorder::{BigEndian, ByteOrder},
+    criterion::{black_box, Criterion},
+    digest::Digest,
+    golomb_set::{EliasFanoGcs, Lookup, UnpackedGcs},
     rand_core::{RngCore, SeedableRng},
     rand_xorshift::XorShiftRng,
     twox_hash::XxHash,
@@ -24,7 +26,7 @@ fn contains_packed(c: &mut Criterion) {
     };
 
     c.bench_function("contains packed", move |b| {
-        b.iter(|| gcs.contains(&[0, 1, 2, 3, 4, 5, 6, 7]))
+        b.iter(|| gcs.contains([0, 1, 2, 3, 4, 5, 6, 7]))
     });
 }
 
@@ -43,9 +45,68 @@ fn contains_unpacked(c: &mut Criterion) {
     };
 
     c.bench_function("contains unpacked", move |b| {
-        b.iter(|| gcs.contains(&[0, 1, 2, 3, 4, 5, 6, 7]))
+        b.iter(|| gcs.contains([0, 1, 2, 3, 4, 5, 6, 7]))
     });
 }
 
-criterion_group!(benches, contains_packed, contains_unpacked);
+fn contains_elias_fano(c: &mut Criterion) {
+    let gcs = {
+        let mut unpacked = UnpackedGcs::<XxHash>::new(8000, 6);
+        let mut rng = XorShiftRng::seed_from_u64(0);
+
+        for _ in 0..8000 {
+            let mut buf = [0u8; 128];
+            rng.fill_bytes(&mut buf);
+            unpacked.insert(&buf[..]).unwrap();
+        }
+
+        EliasFanoGcs::from(&unpacked)
+    };
+
+    c.bench_function("contains elias-fano", move |b| {
+        b.iter(|| gcs.contains([0, 1, 2, 3, 4, 5, 6, 7]))
+    });
+}
+
+fn contains_lookup(c: &mut Criterion) {
+    const NUM_ITEMS: usize = 1_000_000;
+
+    // Extending sorts the hashes once, where inserting them one at a time
+    // would shift the values of the set on every insertion
+    let mut rng = XorShiftRng::seed_from_u64(0);
+    let items = (0..NUM_ITEMS)
+        .map(|_| rng.next_u64().to_be_bytes())
+        .collect::<Vec<_>>();
+    let mut gcs = UnpackedGcs::<XxHash>::new(NUM_ITEMS, 20);
+    gcs.extend(&items).unwrap();
+
+    // Half of the queries are the hashes of items in the set
+    let mut queries = (0..1024).map(|_| rng.next_u64()).collect::<Vec<_>>();
+    for (query, item) in queries.iter_mut().step_by(2).zip(&items) {
+        *query = BigEndian::read_u64(&XxHash::digest(item));
+    }
+
+    for &(name, lookup) in &[
+        ("contains 1M binary", Lookup::Binary),
+        ("contains 1M interpolation", Lookup::Interpolation),
+    ] {
+        gcs.set_lookup(lookup);
+        c.bench_function(name, |b| {
+            b.iter(|| {
+                queries
+                    .iter()
+                    .filter(|&&hash| gcs.contains_hash(black_box(hash)))
+                    .count()
+            })
+        });
+    }
+}
+
+criterion_group!(
+    benches,
+    contains_packed,
+    contains_unpacked,
+    contains_elias_fano,
+    contains_lookup
+);
 criterion_main!(benches);
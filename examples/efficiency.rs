@@ -1,12 +1,12 @@
 use {
-    golomb_set::UnpackedGcs,
+    golomb_set::{BucketedGcs, UnpackedGcs},
     rand_core::{RngCore, SeedableRng},
     rand_xorshift::XorShiftRng,
+    std::f64::consts::E,
     twox_hash::XxHash,
 };
 
 const NUM_ITEMS: usize = 1000;
-const E: f64 = 2.718282;
 
 fn main() {
     println!("plain list: {:?} bytes", 1000 * 32);
@@ -27,13 +27,27 @@ fn main() {
         for _ in 0..NUM_ITEMS {
             let mut buf = [0u8; 32];
             prng.fill_bytes(&mut buf);
-            gcs.insert(&buf).unwrap();
+            gcs.insert(buf).unwrap();
         }
 
+        let gcs = gcs.pack();
         let mut gcs_buf = Vec::new();
-        gcs.pack().write(&mut gcs_buf).unwrap();
+        gcs.write(&mut gcs_buf).unwrap();
 
         println!("GCS: {:?} bytes", gcs_buf.len());
+        println!("{}", gcs.stats());
+
+        // Bucketed GCS, with an average of 64 values per bucket
+        let bucketed = BucketedGcs::new(&gcs, 64);
+        let mut bucketed_buf = Vec::new();
+        bucketed.write(&mut bucketed_buf).unwrap();
+
+        println!(
+            "bucketed GCS: {:?} bytes ({:+.1}% vs. GCS, {} bits of offsets)",
+            bucketed_buf.len(),
+            (bucketed_buf.len() as f64 / gcs_buf.len() as f64 - 1.0) * 100.0,
+            bucketed.offset_bits()
+        );
     }
 
     // Theoretical minimum
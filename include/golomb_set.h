@@ -0,0 +1,165 @@
+#ifndef GOLOMB_SET_H
+#define GOLOMB_SET_H
+
+/* Generated by cbindgen from src/capi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/*
+ The result of a fallible function.
+ */
+typedef enum GcsStatus {
+  /*
+   The function succeeded.
+   */
+  GCS_STATUS_OK,
+  /*
+   A required pointer argument was null.
+   */
+  GCS_STATUS_NULL_POINTER,
+  /*
+   An item was inserted into a set which has been packed.
+   */
+  GCS_STATUS_PACKED,
+  /*
+   An item was inserted into a set which already contains `n` items.
+   */
+  GCS_STATUS_LIMIT_REACHED,
+  /*
+   Data passed to `gcs_from_bytes` was not a valid canonical encoding.
+   */
+  GCS_STATUS_INVALID_DATA,
+  /*
+   The library panicked, which is a bug.
+   */
+  GCS_STATUS_PANIC,
+  /*
+   A digest argument was not one of the values of `GcsDigest`.
+   */
+  GCS_STATUS_INVALID_DIGEST,
+} GcsStatus;
+
+/*
+ The digest used to hash items inserted into and queried from a set.
+
+ Functions take the digest as a `uint32_t` rather than this enum, as C
+ allows any integer to be passed for an enum, and unknown values are
+ rejected.
+ */
+typedef enum GcsDigest {
+  /*
+   MD5, truncated to its first 64 bits.
+   */
+  GCS_DIGEST_MD5 = 0,
+  /*
+   SHA-1, truncated to its first 64 bits.
+   */
+  GCS_DIGEST_SHA1 = 1,
+  /*
+   64 bit xxHash with a seed of 0.
+   */
+  GCS_DIGEST_XX_HASH = 2,
+} GcsDigest;
+
+/*
+ An opaque handle to a packed or unpacked set.
+ */
+typedef struct GcsHandle GcsHandle;
+
+/*
+ Creates a new unpacked set from `n` and `p`, see
+ `UnpackedGcs::new`, hashing items with one of the values of `GcsDigest`.
+
+ Returns null if `digest` is unknown, `n` is 0, `p` is 0 or `n * 2^p` does
+ not fit in 64 bits. Otherwise the returned handle must be freed with
+ `gcs_free`.
+ */
+struct GcsHandle *gcs_new(uint32_t digest, uintptr_t n, uint8_t p);
+
+/*
+ Adds the `len` bytes at `data` to an unpacked set.
+
+ # Errors
+ * `GcsStatus::Packed` if the set has been packed.
+ * `GcsStatus::LimitReached` if the set already contains `n` items.
+ * `GcsStatus::Panic` if the library panics.
+
+ # Safety
+ `gcs` must be a valid handle or null, and `data` must point to `len` readable bytes
+ or be null if `len` is 0.
+ */
+enum GcsStatus gcs_insert(struct GcsHandle *gcs, const uint8_t *data, uintptr_t len);
+
+/*
+ Packs a set in place, after which no more items can be inserted. Packing an
+ already packed set does nothing.
+
+ # Errors
+ * `GcsStatus::Panic` if the library panics.
+
+ # Safety
+ `gcs` must be a valid handle or null.
+ */
+enum GcsStatus gcs_pack(struct GcsHandle *gcs);
+
+/*
+ Returns whether or not the `len` bytes at `data` are contained in a packed
+ or unpacked set. If false the input is definitely not present, if true the
+ input is probably present. Returns false if `gcs` is null or the library
+ panics.
+
+ # Safety
+ `gcs` must be a valid handle or null, and `data` must point to `len`
+ readable bytes or be null if `len` is 0.
+ */
+bool gcs_contains(const struct GcsHandle *gcs, const uint8_t *data, uintptr_t len);
+
+/*
+ Reads a packed set from the `len` bytes of its canonical encoding at
+ `data`, storing its handle in `out`. Items are hashed with one of the values
+ of `GcsDigest`.
+
+ The handle must be freed with `gcs_free`.
+
+ # Errors
+ * `GcsStatus::InvalidDigest` if `digest` is unknown, in which case `out`
+   is set to null.
+ * `GcsStatus::InvalidData` if the data is not a valid canonical encoding,
+   in which case `out` is set to null.
+ * `GcsStatus::Panic` if the library panics.
+
+ # Safety
+ `data` must point to `len` readable bytes, and `out` must be valid for
+ writing a pointer.
+ */
+enum GcsStatus gcs_from_bytes(uint32_t digest,
+                              const uint8_t *data,
+                              uintptr_t len,
+                              struct GcsHandle **out);
+
+/*
+ Writes the canonical encoding of a packed or unpacked set to the buffer of
+ `len` bytes at `buf`, returning the length of the encoding.
+
+ Nothing is written if the encoding is longer than `len`, so the required
+ length can be found by passing a null `buf` and a `len` of 0. Returns 0 if
+ `gcs` is null or the library panics.
+
+ # Safety
+ `gcs` must be a valid handle or null, and `buf` must point to `len` writable
+ bytes or be null if `len` is 0.
+ */
+uintptr_t gcs_to_bytes(const struct GcsHandle *gcs, uint8_t *buf, uintptr_t len);
+
+/*
+ Frees a set. Freeing a null handle does nothing.
+
+ # Safety
+ `gcs` must be a valid handle or null, and must not be used afterwards.
+ */
+void gcs_free(struct GcsHandle *gcs);
+
+#endif  /* GOLOMB_SET_H */
@@ -0,0 +1,102 @@
+//! Traits shared by the representations of sets.
+
+use {
+    crate::{stats::values_false_positive_rate, Error, Gcs, UnpackedGcs},
+    digest::Digest,
+};
+
+/// A set which answers membership queries with a small probability of false
+/// positives, implemented by every representation of a set in this crate.
+///
+/// The trait is object safe, so code can accept a `&dyn ApproximateSet`
+/// without depending on the representation.
+///
+/// ```rust
+/// use {
+///     golomb_set::{ApproximateSet, UnpackedGcs},
+///     twox_hash::XxHash,
+/// };
+///
+/// let mut gcs = UnpackedGcs::<XxHash>::new(100, 8);
+/// gcs.extend((0..100u32).map(u32::to_be_bytes)).unwrap();
+/// let packed = gcs.pack();
+///
+/// let sets: [&dyn ApproximateSet; 2] = [&gcs, &packed];
+/// for set in &sets {
+///     assert!(set.contains(&7u32.to_be_bytes()));
+///     assert_eq!(set.len(), 100);
+/// }
+/// ```
+pub trait ApproximateSet {
+    /// Returns whether or not an input is contained in the set. If false the
+    /// input is definitely not present, if true the input is probably present.
+    fn contains(&self, input: &[u8]) -> bool;
+
+    /// Returns the number of values in the set, including duplicates.
+    fn len(&self) -> usize;
+
+    /// Returns whether or not the set contains no values.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the estimated probability of a false positive, given the
+    /// number of distinct values currently in the set.
+    fn false_positive_rate(&self) -> f64;
+
+    /// Returns an iterator over the hashed values stored in the set, reduced
+    /// into the range `[0, n * 2^p)`, in ascending order.
+    fn iter_hashes(&self) -> Box<dyn Iterator<Item = u64> + '_>;
+}
+
+/// An [`ApproximateSet`] which items can be inserted into.
+pub trait MutableApproximateSet: ApproximateSet {
+    /// Adds an entry to the set, returning whether the value was not already
+    /// present.
+    ///
+    /// # Errors
+    /// * If the set cannot hold any more items.
+    fn insert(&mut self, input: &[u8]) -> Result<bool, Error>;
+}
+
+impl<D: Digest> ApproximateSet for UnpackedGcs<D> {
+    fn contains(&self, input: &[u8]) -> bool {
+        UnpackedGcs::contains(self, input)
+    }
+
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    fn false_positive_rate(&self) -> f64 {
+        values_false_positive_rate(self.n, self.p, self.values.iter())
+    }
+
+    fn iter_hashes(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        Box::new(self.values.iter())
+    }
+}
+
+impl<D: Digest> MutableApproximateSet for UnpackedGcs<D> {
+    fn insert(&mut self, input: &[u8]) -> Result<bool, Error> {
+        UnpackedGcs::insert(self, input)
+    }
+}
+
+impl<D: Digest> ApproximateSet for Gcs<D> {
+    fn contains(&self, input: &[u8]) -> bool {
+        Gcs::contains(self, input)
+    }
+
+    fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    fn false_positive_rate(&self) -> f64 {
+        values_false_positive_rate(self.n, self.p, self.iter())
+    }
+
+    fn iter_hashes(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        Box::new(self.iter())
+    }
+}
@@ -0,0 +1,69 @@
+//! Asynchronous reading, writing and construction of sets, enabled by the
+//! `async` feature.
+
+use {
+    crate::{digest_value, Error, Gcs, UnpackedGcs},
+    digest::Digest,
+    futures_util::{pin_mut, stream::Stream, StreamExt},
+    tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+};
+
+impl<D: Digest> UnpackedGcs<D> {
+    /// Hashes items from an asynchronous stream and inserts them into the set.
+    ///
+    /// The whole stream is consumed before any items are inserted, and if
+    /// inserting them would exceed the limit of `n` items, none of them are
+    /// inserted.
+    ///
+    /// # Errors
+    /// * If more than `n` items would have been inserted.
+    pub async fn extend_stream<S, A>(&mut self, inputs: S) -> Result<(), Error>
+    where
+        S: Stream<Item = A>,
+        A: AsRef<[u8]>,
+    {
+        pin_mut!(inputs);
+
+        let mut values = Vec::new();
+        while let Some(input) = inputs.next().await {
+            values.push(digest_value::<D>(self.n as u64, self.p, input.as_ref()));
+        }
+        values.sort_unstable();
+
+        self.merge_sorted(values)
+    }
+}
+
+impl<D: Digest> Gcs<D> {
+    /// Read a packed `Gcs` from an asynchronous reader, as written by
+    /// [`write_counted`](Gcs::write_counted) or
+    /// [`write_async`](Gcs::write_async).
+    ///
+    /// # Errors
+    /// * If there is an error reading data from `reader`.
+    /// * If `n` or `p` are invalid, see [`Error::Parameters`].
+    /// * If the data does not contain the number of values given in its
+    ///   header.
+    pub async fn from_async_reader<R: AsyncRead + Unpin>(
+        reader: &mut R,
+        n: usize,
+        p: u8,
+    ) -> Result<Self, Error> {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).await?;
+
+        Self::from_vec(buf, n, p)
+    }
+
+    /// Writes a packed `Gcs` to an asynchronous writer, in the same format as
+    /// [`write_counted`](Gcs::write_counted).
+    ///
+    /// # Errors
+    /// * If there is an error writing data to `writer`.
+    pub async fn write_async<W: AsyncWrite + Unpin>(&self, writer: &mut W) -> Result<(), Error> {
+        let mut buf = Vec::new();
+        self.write_counted(&mut buf)?;
+        writer.write_all(&buf).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,322 @@
+//! Command-line tool for building, querying and inspecting Golomb Coded Set files.
+
+use {
+    digest::Digest,
+    failure::{bail, format_err, Error},
+    golomb_set::{Gcs, IndexedGcs, UnpackedGcs},
+    md5::Md5,
+    sha1::Sha1,
+    std::{
+        fs::{self, File},
+        io::{self, BufRead, BufReader, BufWriter, Read, Write},
+        path::PathBuf,
+        process,
+        str::FromStr,
+    },
+    structopt::StructOpt,
+    twox_hash::XxHash,
+};
+
+#[derive(StructOpt)]
+#[structopt(
+    name = "gcs",
+    about = "Build, query and inspect Golomb Coded Set files"
+)]
+enum Command {
+    /// Builds a set from newline-delimited items
+    Build {
+        /// Hash algorithm: md5, sha1 or xxhash
+        #[structopt(long, default_value = "xxhash")]
+        hash: Algorithm,
+        /// Maximum number of items, defaults to the number of items read
+        #[structopt(short, parse(try_from_str = parse_positive))]
+        n: Option<usize>,
+        /// Probability of a false positive once full is 1/2^p
+        #[structopt(short, parse(try_from_str = parse_p))]
+        p: u8,
+        /// Format of the set file: canonical, raw or indexed
+        #[structopt(long, default_value = "canonical")]
+        format: Format,
+        /// Number of values between index entries of an indexed set
+        #[structopt(long, default_value = "64", parse(try_from_str = parse_positive))]
+        granularity: usize,
+        /// File to read items from, defaults to stdin
+        #[structopt(short, long, parse(from_os_str))]
+        input: Option<PathBuf>,
+        /// File to write the set to, defaults to stdout
+        #[structopt(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+    /// Checks whether items are in a set, exiting with 1 if any are not
+    Query {
+        /// Hash algorithm: md5, sha1 or xxhash
+        #[structopt(long, default_value = "xxhash")]
+        hash: Algorithm,
+        #[structopt(flatten)]
+        set: SetFile,
+        /// Items to check, read from stdin if none are given
+        items: Vec<String>,
+    },
+    /// Prints the parameters and size of a set
+    Inspect {
+        #[structopt(flatten)]
+        set: SetFile,
+    },
+    /// Converts a set between formats
+    Convert {
+        #[structopt(flatten)]
+        set: SetFile,
+        /// Format to convert to: canonical, raw or indexed
+        #[structopt(long)]
+        to: Format,
+        /// Number of values between index entries of an indexed set
+        #[structopt(long, default_value = "64", parse(try_from_str = parse_positive))]
+        granularity: usize,
+        /// File to write the set to, defaults to stdout
+        #[structopt(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+}
+
+// A set file given as an argument, not documented to avoid overriding the
+// help of the subcommands it is flattened into
+#[derive(StructOpt)]
+struct SetFile {
+    /// The set file
+    #[structopt(parse(from_os_str))]
+    path: PathBuf,
+    /// Format of the set file: canonical, raw or indexed
+    #[structopt(long, default_value = "canonical")]
+    format: Format,
+    /// Maximum number of items of a raw set
+    #[structopt(short, parse(try_from_str = parse_positive))]
+    n: Option<usize>,
+    /// Probability of a false positive of a raw set
+    #[structopt(short, parse(try_from_str = parse_p))]
+    p: Option<u8>,
+}
+
+impl SetFile {
+    fn read<D: Digest>(&self) -> Result<Gcs<D>, Error> {
+        let mut reader = BufReader::new(File::open(&self.path)?);
+
+        match self.format {
+            Format::Canonical => Ok(Gcs::from_canonical_reader(&mut reader)?),
+            Format::Raw => match (self.n, self.p) {
+                (Some(n), Some(p)) => Ok(Gcs::from_reader(&mut reader, n, p)?),
+                _ => bail!("`-n` and `-p` are required for raw sets"),
+            },
+            Format::Indexed => Ok(IndexedGcs::from_reader(&mut reader)?.into_gcs()),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Algorithm {
+    Md5,
+    Sha1,
+    XxHash,
+}
+
+impl FromStr for Algorithm {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "md5" => Ok(Algorithm::Md5),
+            "sha1" => Ok(Algorithm::Sha1),
+            "xxhash" => Ok(Algorithm::XxHash),
+            _ => Err(format_err!("Unknown hash algorithm `{}`", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    /// The canonical encoding, including a header with the parameters
+    Canonical,
+    /// Only the Golomb-Rice encoded values, as written by rasky's implementation
+    Raw,
+    /// A header and an index of buckets followed by the raw values
+    Indexed,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "canonical" => Ok(Format::Canonical),
+            "raw" => Ok(Format::Raw),
+            "indexed" => Ok(Format::Indexed),
+            _ => Err(format_err!("Unknown format `{}`", s)),
+        }
+    }
+}
+
+/// Parses a number which must be greater than 0.
+fn parse_positive(s: &str) -> Result<usize, Error> {
+    match s.parse()? {
+        0 => bail!("must be greater than 0"),
+        value => Ok(value),
+    }
+}
+
+/// Parses `p`, which must be between 1 and 63.
+fn parse_p(s: &str) -> Result<u8, Error> {
+    match s.parse()? {
+        p @ 1..=63 => Ok(p),
+        _ => bail!("must be between 1 and 63"),
+    }
+}
+
+fn main() {
+    match run(Command::from_args()) {
+        Ok(code) => process::exit(code),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(2);
+        }
+    }
+}
+
+/// Runs a command, returning the exit code.
+fn run(command: Command) -> Result<i32, Error> {
+    match command {
+        Command::Build {
+            hash,
+            n,
+            p,
+            format,
+            granularity,
+            input,
+            output,
+        } => {
+            let items = match input {
+                Some(path) => read_lines(File::open(path)?)?,
+                None => read_lines(io::stdin())?,
+            };
+            // An empty set still needs a valid `n`
+            let n = n.unwrap_or_else(|| items.len().max(1));
+            if 2u64
+                .checked_pow(u32::from(p))
+                .and_then(|range| range.checked_mul(n as u64))
+                .is_none()
+            {
+                bail!("`n * 2^p` must fit in 64 bits");
+            }
+
+            let output = Output {
+                format,
+                granularity,
+                path: output,
+            };
+            match hash {
+                Algorithm::Md5 => build::<Md5>(&items, n, p, output),
+                Algorithm::Sha1 => build::<Sha1>(&items, n, p, output),
+                Algorithm::XxHash => build::<XxHash>(&items, n, p, output),
+            }
+        }
+        Command::Query { hash, set, items } => {
+            let items = if items.is_empty() {
+                read_lines(io::stdin())?
+            } else {
+                items
+            };
+
+            match hash {
+                Algorithm::Md5 => query(&set.read::<Md5>()?, &items),
+                Algorithm::Sha1 => query(&set.read::<Sha1>()?, &items),
+                Algorithm::XxHash => query(&set.read::<XxHash>()?, &items),
+            }
+        }
+        // The hash algorithm is irrelevant when no items are hashed
+        Command::Inspect { set } => inspect(&set.read::<XxHash>()?, fs::metadata(&set.path)?.len()),
+        Command::Convert {
+            set,
+            to,
+            granularity,
+            output,
+        } => {
+            let output = Output {
+                format: to,
+                granularity,
+                path: output,
+            };
+            output.write(set.read::<XxHash>()?)?;
+            Ok(0)
+        }
+    }
+}
+
+fn build<D: Digest>(items: &[String], n: usize, p: u8, output: Output) -> Result<i32, Error> {
+    let mut gcs = UnpackedGcs::<D>::new(n, p);
+    gcs.extend(items)?;
+
+    output.write(gcs.pack())?;
+    Ok(0)
+}
+
+fn query<D: Digest>(gcs: &Gcs<D>, items: &[String]) -> Result<i32, Error> {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+
+    let mut code = 0;
+    for item in items {
+        if gcs.contains(item) {
+            writeln!(stdout, "{}\tprobably present", item)?;
+        } else {
+            writeln!(stdout, "{}\tabsent", item)?;
+            code = 1;
+        }
+    }
+
+    Ok(code)
+}
+
+/// Prints the parameters of a set, and the size of the `size` byte file it
+/// was read from.
+fn inspect<D: Digest>(gcs: &Gcs<D>, size: u64) -> Result<i32, Error> {
+    let len = gcs.iter().count();
+
+    println!("n: {}", gcs.n());
+    println!("p: {}", gcs.p());
+    println!("elements: {}", len);
+    println!("size: {} bytes", size);
+    if len > 0 {
+        println!("bits per element: {:.3}", (size * 8) as f64 / len as f64);
+    }
+
+    Ok(0)
+}
+
+/// Where and how a set is written.
+struct Output {
+    format: Format,
+    /// Number of values between index entries of an indexed set.
+    granularity: usize,
+    /// File to write the set to, or stdout if `None`.
+    path: Option<PathBuf>,
+}
+
+impl Output {
+    fn write<D: Digest>(self, gcs: Gcs<D>) -> Result<(), Error> {
+        let mut writer: Box<dyn Write> = match self.path {
+            Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+            None => Box::new(io::stdout()),
+        };
+
+        match self.format {
+            Format::Canonical => gcs.write_canonical(&mut writer)?,
+            Format::Raw => gcs.write(&mut writer)?,
+            Format::Indexed => IndexedGcs::new(gcs, self.granularity).write(&mut writer)?,
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+fn read_lines<R: Read>(reader: R) -> Result<Vec<String>, Error> {
+    Ok(BufReader::new(reader).lines().collect::<Result<_, _>>()?)
+}
@@ -0,0 +1,365 @@
+//! Interoperability with Bloom filters from the `bloom` crate, enabled by the
+//! `bloom` feature.
+//!
+//! [`BloomGcs`] converts a set into a Bloom filter with the same false
+//! positive rate and [`compare`] reports the size and query speed of both,
+//! while [`HybridFilter`] combines a Bloom filter of recent inserts with a
+//! packed set.
+
+use {
+    crate::{
+        digest_value, stats::values_false_positive_rate, ApproximateSet, Error, Gcs,
+        MutableApproximateSet, UnpackedGcs,
+    },
+    ::bloom::{BloomFilter, ASMS},
+    digest::Digest,
+    std::{
+        fmt,
+        marker::PhantomData,
+        time::{Duration, Instant},
+    },
+};
+
+/// A Bloom filter containing the hashed values of a set, reduced into the
+/// range `[0, n * 2^p)` like those of an [`UnpackedGcs`].
+///
+/// The filter is sized for the estimated false positive rate of the set it is
+/// converted from. Inputs are reduced before querying the filter, so an input
+/// is also a false positive if its value collides with one in the set, and
+/// the total false positive rate is up to twice that of the set.
+pub struct BloomGcs<D: Digest> {
+    n: usize,
+    p: u8,
+    len: usize,
+    filter: BloomFilter,
+    digest: PhantomData<D>,
+}
+
+impl<D: Digest> BloomGcs<D> {
+    /// Returns whether or not an input is contained in the filter. If false
+    /// the input is definitely not present, if true the input is probably
+    /// present.
+    pub fn contains<A: AsRef<[u8]>>(&self, input: A) -> bool {
+        self.contains_reduced(digest_value::<D>(self.n as u64, self.p, input.as_ref()))
+    }
+
+    /// Returns whether or not a value in the range `[0, n * 2^p)` is contained
+    /// in the filter, see [`UnpackedGcs::insert_reduced`].
+    pub fn contains_reduced(&self, value: u64) -> bool {
+        self.filter.contains(&value)
+    }
+
+    /// Returns the number of values inserted into the filter.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether or not the filter contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of bits of the filter.
+    pub fn num_bits(&self) -> usize {
+        self.filter.num_bits()
+    }
+
+    /// Returns the number of hash functions of the filter.
+    pub fn num_hashes(&self) -> u32 {
+        self.filter.num_hashes()
+    }
+}
+
+impl<D: Digest> From<&UnpackedGcs<D>> for BloomGcs<D> {
+    fn from(gcs: &UnpackedGcs<D>) -> Self {
+        let rate = ApproximateSet::false_positive_rate(gcs).max(f64::from(f32::MIN_POSITIVE));
+        let mut filter = BloomFilter::with_rate(rate as f32, gcs.values.len().max(1) as u32);
+        for value in gcs.values.iter() {
+            filter.insert(&value);
+        }
+
+        Self {
+            n: gcs.n,
+            p: gcs.p,
+            len: gcs.values.len(),
+            filter,
+            digest: PhantomData,
+        }
+    }
+}
+
+/// The sizes and query speeds of a set and the equivalent Bloom filter,
+/// returned by [`compare`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comparison {
+    /// The estimated false positive rate of the set, which the Bloom filter is
+    /// sized for.
+    pub false_positive_rate: f64,
+    /// The number of bits used by the Golomb-Rice encoding of the packed set.
+    pub packed_bits: usize,
+    /// The number of bits used by the values of the unpacked set.
+    pub unpacked_bits: usize,
+    /// The number of bits of the Bloom filter.
+    pub bloom_bits: usize,
+    /// The number of hash functions of the Bloom filter.
+    pub bloom_hashes: u32,
+    /// The average time taken to query the packed set.
+    pub packed_query: Duration,
+    /// The average time taken to query the unpacked set.
+    pub unpacked_query: Duration,
+    /// The average time taken to query the Bloom filter.
+    pub bloom_query: Duration,
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "false positive rate: {:.4}%",
+            self.false_positive_rate * 100.0
+        )?;
+        writeln!(
+            f,
+            "packed: {} bits, {:?} per query",
+            self.packed_bits, self.packed_query
+        )?;
+        writeln!(
+            f,
+            "unpacked: {} bits, {:?} per query",
+            self.unpacked_bits, self.unpacked_query
+        )?;
+        write!(
+            f,
+            "bloom: {} bits ({} hashes), {:?} per query",
+            self.bloom_bits, self.bloom_hashes, self.bloom_query
+        )
+    }
+}
+
+/// Converts a set into a [`BloomGcs`] and compares their sizes, along with
+/// the average time taken to query the packed set, the unpacked set and the
+/// Bloom filter with each of `queries`.
+///
+/// ```rust
+/// use {
+///     golomb_set::{bloom::compare, UnpackedGcs},
+///     twox_hash::XxHash,
+/// };
+///
+/// let mut gcs = UnpackedGcs::<XxHash>::new(1000, 8);
+/// gcs.extend((0..1000u32).map(u32::to_be_bytes)).unwrap();
+///
+/// let comparison = compare(&gcs, (0..100u32).map(u32::to_be_bytes));
+/// assert!(comparison.packed_bits < comparison.bloom_bits);
+/// println!("{}", comparison);
+/// ```
+pub fn compare<D, I, A>(gcs: &UnpackedGcs<D>, queries: I) -> Comparison
+where
+    D: Digest,
+    I: IntoIterator<Item = A>,
+    A: AsRef<[u8]>,
+{
+    let queries = queries.into_iter().collect::<Vec<_>>();
+    let packed = gcs.pack();
+    let bloom = BloomGcs::from(gcs);
+
+    Comparison {
+        false_positive_rate: ApproximateSet::false_positive_rate(gcs),
+        packed_bits: packed.data.len(),
+        unpacked_bits: gcs.values.len() * gcs.bits_per_value() as usize,
+        bloom_bits: bloom.num_bits(),
+        bloom_hashes: bloom.num_hashes(),
+        packed_query: time(&queries, |input| packed.contains(input)),
+        unpacked_query: time(&queries, |input| gcs.contains(input)),
+        bloom_query: time(&queries, |input| bloom.contains(input)),
+    }
+}
+
+/// Returns the average time taken to call `contains` with each query.
+fn time<A, F>(queries: &[A], contains: F) -> Duration
+where
+    A: AsRef<[u8]>,
+    F: Fn(&[u8]) -> bool,
+{
+    let start = Instant::now();
+    let positives = queries
+        .iter()
+        .filter(|input| contains(input.as_ref()))
+        .count();
+    let elapsed = start.elapsed();
+
+    // Keep the queries from being optimised away
+    std::hint::black_box(positives);
+    elapsed / queries.len().max(1) as u32
+}
+
+/// A set which inserts items into a small Bloom filter, and periodically
+/// folds them into a packed [`Gcs`].
+///
+/// Inserting into a packed set requires encoding all of its values again, so
+/// recent inserts are kept in a Bloom filter along with a sorted list of their
+/// values. Once the filter holds `capacity` values they are merged into the
+/// packed set, which also happens when calling [`fold`](HybridFilter::fold).
+/// Queries check the Bloom filter, then the list of values for those it may
+/// contain, and then the packed set, so the Bloom filter adds no false
+/// positives.
+pub struct HybridFilter<D: Digest> {
+    gcs: Gcs<D>,
+    /// The number of values in `gcs`.
+    folded: usize,
+    front: BloomFilter,
+    /// The values in `front`, in ascending order.
+    pending: Vec<u64>,
+    capacity: usize,
+}
+
+impl<D: Digest> HybridFilter<D> {
+    /// Creates a new `HybridFilter` from `n` and `p`, like
+    /// [`UnpackedGcs::new`], which folds inserted items into the packed set
+    /// after every `capacity` inserts.
+    ///
+    /// The Bloom filter is sized for a false positive rate of `1 / 2^p` when
+    /// it is full.
+    ///
+    /// # Panics
+    /// * Panics if `capacity == 0`.
+    pub fn new(n: usize, p: u8, capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity cannot be 0");
+
+        Self {
+            gcs: UnpackedGcs::new(n, p).pack(),
+            folded: 0,
+            front: front(p, capacity),
+            pending: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Adds an entry to the set, folding the Bloom filter into the packed set
+    /// if it is full.
+    ///
+    /// Returns false if the value is already present, either in the Bloom
+    /// filter or in the packed set, in which case it is not inserted again.
+    ///
+    /// # Errors
+    /// * If more than `n` items have been inserted.
+    pub fn insert<A: AsRef<[u8]>>(&mut self, input: A) -> Result<bool, Error> {
+        let value = digest_value::<D>(self.gcs.n as u64, self.gcs.p, input.as_ref());
+        if self.contains_reduced(value) {
+            return Ok(false);
+        }
+        if self.folded + self.pending.len() >= self.gcs.n {
+            self.fold()?;
+            if self.folded >= self.gcs.n {
+                return Err(Error::LimitReached);
+            }
+        }
+
+        self.front.insert(&value);
+        let index = self.pending.partition_point(|&other| other < value);
+        self.pending.insert(index, value);
+        if self.pending.len() >= self.capacity {
+            self.fold()?;
+        }
+
+        Ok(true)
+    }
+
+    /// Returns whether or not an input is contained in the set. If false the
+    /// input is definitely not present, if true the input is probably present.
+    pub fn contains<A: AsRef<[u8]>>(&self, input: A) -> bool {
+        self.contains_reduced(digest_value::<D>(
+            self.gcs.n as u64,
+            self.gcs.p,
+            input.as_ref(),
+        ))
+    }
+
+    /// Returns whether or not a value in the range `[0, n * 2^p)` is contained
+    /// in the set, see [`UnpackedGcs::insert_reduced`].
+    pub fn contains_reduced(&self, value: u64) -> bool {
+        // The Bloom filter avoids searching the pending values in most cases
+        (self.front.contains(&value) && self.pending.binary_search(&value).is_ok())
+            || self.gcs.contains_reduced(value)
+    }
+
+    /// Merges the values in the Bloom filter into the packed set, and clears
+    /// the filter.
+    ///
+    /// # Errors
+    /// * If the packed set would contain more than `n` values.
+    pub fn fold(&mut self) -> Result<(), Error> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut unpacked = self.gcs.unpack();
+        unpacked.merge_sorted(self.pending.clone())?;
+
+        self.folded = unpacked.values.len();
+        self.gcs = unpacked.pack();
+        self.front.clear();
+        self.pending.clear();
+        Ok(())
+    }
+
+    /// Returns the packed set, which does not include the values in the Bloom
+    /// filter until they are folded into it.
+    pub fn gcs(&self) -> &Gcs<D> {
+        &self.gcs
+    }
+
+    /// Returns the number of values in the Bloom filter.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Folds the values in the Bloom filter into the packed set, and returns
+    /// the packed set.
+    ///
+    /// # Errors
+    /// * If the packed set would contain more than `n` values.
+    pub fn into_gcs(mut self) -> Result<Gcs<D>, Error> {
+        self.fold()?;
+        Ok(self.gcs)
+    }
+}
+
+impl<D: Digest> ApproximateSet for HybridFilter<D> {
+    fn contains(&self, input: &[u8]) -> bool {
+        HybridFilter::contains(self, input)
+    }
+
+    fn len(&self) -> usize {
+        self.folded + self.pending.len()
+    }
+
+    fn false_positive_rate(&self) -> f64 {
+        values_false_positive_rate(self.gcs.n, self.gcs.p, self.iter_hashes())
+    }
+
+    fn iter_hashes(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        let mut pending = self.pending.iter().cloned().peekable();
+
+        let mut folded = self.gcs.iter().peekable();
+        Box::new(std::iter::from_fn(move || {
+            match (folded.peek(), pending.peek()) {
+                (Some(a), Some(b)) if a <= b => folded.next(),
+                (_, Some(_)) => pending.next(),
+                _ => folded.next(),
+            }
+        }))
+    }
+}
+
+impl<D: Digest> MutableApproximateSet for HybridFilter<D> {
+    fn insert(&mut self, input: &[u8]) -> Result<bool, Error> {
+        HybridFilter::insert(self, input)
+    }
+}
+
+/// Creates the Bloom filter of a `HybridFilter`.
+fn front(p: u8, capacity: usize) -> BloomFilter {
+    let rate = 0.5f64.powi(i32::from(p)).max(f64::from(f32::MIN_POSITIVE));
+    BloomFilter::with_rate(rate as f32, capacity as u32)
+}
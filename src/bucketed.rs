@@ -0,0 +1,338 @@
+//! Packed sets partitioned into buckets of the hash range, which are encoded
+//! independently.
+
+use {
+    crate::{
+        bits_from_vec, check_parameters, digest_value, encode, golomb_decode, infer_policy, range,
+        reduce, stats::values_false_positive_rate, ApproximateSet, DuplicatePolicy, Error, Gcs,
+        Iter,
+    },
+    bitvec::prelude::BitVec,
+    byteorder::{BigEndian, ReadBytesExt, WriteBytesExt},
+    digest::Digest,
+    std::{
+        io::{Read, Write},
+        marker::PhantomData,
+    },
+};
+
+/// Magic bytes at the start of an encoded [`BucketedGcs`].
+const BUCKETED_MAGIC: [u8; 4] = *b"GCSB";
+
+/// A packed Golomb-coded Set whose hash range `[0, n * 2^p)` is partitioned
+/// into buckets of equal width, each of which is Golomb-Rice encoded
+/// independently.
+///
+/// A table of the bit offset of each bucket, packed into the minimum number of
+/// bits, allows queries to decode only the bucket which could contain the
+/// input. Unlike an [`IndexedGcs`](crate::IndexedGcs), finding the bucket does
+/// not require a search, and the expected number of values decoded does not
+/// depend on the size of the set.
+///
+/// Like a [`Gcs`], the duplicate policy is inferred when reading a set and is
+/// not compared for equality.
+#[derive(Clone, Debug)]
+pub struct BucketedGcs<D: Digest> {
+    n: usize,
+    p: u8,
+    policy: DuplicatePolicy,
+    /// The number of bits of each entry of `offsets`.
+    width: u8,
+    /// The `buckets + 1` offsets of the start of each bucket in `data`,
+    /// followed by the length of `data`.
+    offsets: BitVec,
+    data: BitVec,
+    digest: PhantomData<D>,
+}
+
+impl<D: Digest> PartialEq for BucketedGcs<D> {
+    fn eq(&self, other: &Self) -> bool {
+        self.n == other.n
+            && self.p == other.p
+            && self.width == other.width
+            && self.offsets == other.offsets
+            && self.data == other.data
+    }
+}
+
+impl<D: Digest> BucketedGcs<D> {
+    /// Creates a `BucketedGcs` from a packed `Gcs`, with enough buckets for
+    /// each to contain an average of `granularity` of its `n` values.
+    ///
+    /// # Panics
+    /// * Panics if `granularity == 0`.
+    pub fn new(gcs: &Gcs<D>, granularity: usize) -> Self {
+        assert!(granularity > 0, "granularity cannot be 0");
+
+        let buckets = gcs.n.div_ceil(granularity).max(1) as u64;
+        let width = bucket_width(gcs.n, gcs.p, buckets);
+
+        let mut data = BitVec::new();
+        let mut starts = Vec::with_capacity(buckets as usize + 1);
+        let mut values = gcs.iter().peekable();
+        for bucket in 0..buckets {
+            starts.push(data.len() as u64);
+
+            let end = (bucket + 1).saturating_mul(width);
+            let bucket_values = std::iter::from_fn(|| values.next_if(|&value| value < end));
+            let (_, mut encoded) = encode(bucket_values, gcs.p, bucket * width);
+            data.append(&mut encoded);
+        }
+        starts.push(data.len() as u64);
+
+        let bits = (64 - (data.len() as u64).leading_zeros() as u8).max(1);
+        let mut offsets = BitVec::new();
+        for start in starts {
+            for i in (0..bits).rev() {
+                offsets.push(start >> i & 1 == 1);
+            }
+        }
+
+        Self {
+            n: gcs.n,
+            p: gcs.p,
+            policy: gcs.policy,
+            width: bits,
+            offsets,
+            data,
+            digest: PhantomData,
+        }
+    }
+
+    /// Read a `BucketedGcs` from any Reader, as written by
+    /// [`write`](BucketedGcs::write).
+    ///
+    /// # Errors
+    /// * If there is an error reading data from `reader`.
+    /// * If the header or offset table is malformed.
+    /// * If `n` or `p` are invalid, see [`Error::Parameters`].
+    /// * If a bucket is not a valid Golomb-Rice encoding of values within the
+    ///   bucket.
+    pub fn from_reader<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != BUCKETED_MAGIC {
+            return Err(Error::Header);
+        }
+
+        let p = reader.read_u8()?;
+        let n = reader.read_u64::<BigEndian>()?;
+        check_parameters(n, p)?;
+        let n = n as usize;
+        let buckets = reader.read_u64::<BigEndian>()?;
+        let width = reader.read_u8()?;
+        if buckets == 0 || width == 0 || width > 64 {
+            return Err(Error::Header);
+        }
+
+        let bits = buckets
+            .checked_add(1)
+            .and_then(|entries| entries.checked_mul(u64::from(width)))
+            .ok_or(Error::Header)?;
+        let mut offsets = bits_from_vec(read_len(reader, bits.div_ceil(8))?);
+        offsets.truncate(bits as usize);
+
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+
+        let mut gcs = Self {
+            n,
+            p,
+            policy: DuplicatePolicy::default(),
+            width,
+            offsets,
+            data: bits_from_vec(buf),
+            digest: PhantomData,
+        };
+        gcs.validate()?;
+        let end = gcs.offset(gcs.buckets());
+        gcs.data.truncate(end);
+        gcs.policy = infer_policy(gcs.iter());
+        Ok(gcs)
+    }
+
+    /// Writes a `BucketedGcs` to a Writer.
+    ///
+    /// It consists of the following fields, where `w` is the number of bits
+    /// of each offset:
+    ///
+    /// | Length  | Content                                                   |
+    /// |---------|-----------------------------------------------------------|
+    /// | 4 bytes | The magic bytes `GCSB`                                    |
+    /// | 1 byte  | `p`                                                       |
+    /// | 8 bytes | `n`, as a big endian integer                              |
+    /// | 8 bytes | The number of buckets `b`, as a big endian integer         |
+    /// | 1 byte  | `w`                                                       |
+    /// | varies  | The `b + 1` offsets in bits of the start of each bucket followed by the end of the last bucket, as `w` bit big endian integers padded with zero bits to a whole number of bytes |
+    /// | rest    | The Golomb-Rice encoding of each bucket's values in ascending order, starting from the bucket's lowest value, padded with zero bits to a whole number of bytes |
+    ///
+    /// # Errors
+    /// * If there is an error writing data to `writer`.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        writer.write_all(&BUCKETED_MAGIC)?;
+        writer.write_u8(self.p)?;
+        writer.write_u64::<BigEndian>(self.n as u64)?;
+        writer.write_u64::<BigEndian>(self.buckets())?;
+        writer.write_u8(self.width)?;
+        writer.write_all(&self.offsets.clone().into_vec())?;
+        writer.write_all(&self.data.clone().into_vec())?;
+        Ok(())
+    }
+
+    /// Returns whether or not an input is contained in the set. If false the
+    /// input is definitely not present, if true the input is probably present.
+    ///
+    /// Only the bucket which could contain the input is decoded.
+    pub fn contains<A: AsRef<[u8]>>(&self, input: A) -> bool {
+        self.contains_reduced(digest_value::<D>(self.n as u64, self.p, input.as_ref()))
+    }
+
+    /// Returns whether or not a pre-computed 64 bit hash is contained in the
+    /// set, see [`UnpackedGcs::insert_hash`](crate::UnpackedGcs::insert_hash).
+    pub fn contains_hash(&self, hash: u64) -> bool {
+        self.contains_reduced(reduce(self.n as u64, self.p, hash))
+    }
+
+    /// Returns whether or not a value in the range `[0, n * 2^p)` is contained
+    /// in the set, see
+    /// [`UnpackedGcs::insert_reduced`](crate::UnpackedGcs::insert_reduced).
+    pub fn contains_reduced(&self, input: u64) -> bool {
+        let bucket = input / bucket_width(self.n, self.p, self.buckets());
+        if bucket >= self.buckets() {
+            return false;
+        }
+
+        self.bucket(bucket)
+            .take_while(|&value| value <= input)
+            .any(|value| value == input)
+    }
+
+    /// Returns an iterator over the hashed values stored in the set, in
+    /// ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..self.buckets()).flat_map(move |bucket| self.bucket(bucket))
+    }
+
+    /// Returns the packed set without buckets.
+    pub fn to_gcs(&self) -> Gcs<D> {
+        let (_, data) = encode(self.iter(), self.p, 0);
+
+        Gcs {
+            n: self.n,
+            p: self.p,
+            policy: self.policy,
+            data,
+            digest: PhantomData,
+        }
+    }
+
+    /// Returns the number of buckets.
+    pub fn buckets(&self) -> u64 {
+        self.offsets.len() as u64 / u64::from(self.width) - 1
+    }
+
+    /// Returns the number of bits used by the offset table.
+    pub fn offset_bits(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Returns `n`, the maximum number of elements.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Returns `p`, where the false positive probability is `1 / 2^p`.
+    pub fn p(&self) -> u8 {
+        self.p
+    }
+
+    /// Returns the offset in bits of the start of a bucket, or of the end of
+    /// the last bucket.
+    fn offset(&self, i: u64) -> usize {
+        let start = i as usize * self.width as usize;
+        self.offsets[start..start + self.width as usize]
+            .iter()
+            .fold(0, |offset, bit| offset << 1 | bit as usize)
+    }
+
+    /// Returns an iterator over the values of a bucket.
+    fn bucket(&self, bucket: u64) -> Iter<'_> {
+        let (start, end) = (self.offset(bucket), self.offset(bucket + 1));
+
+        Iter {
+            bits: self.data[start..end].iter().peekable(),
+            p: self.p,
+            last: bucket * bucket_width(self.n, self.p, self.buckets()),
+        }
+    }
+
+    /// Checks that the offsets are in order and within the data, and that each
+    /// bucket only contains valid encodings of values within the bucket.
+    fn validate(&self) -> Result<(), Error> {
+        let (buckets, width) = (self.buckets(), bucket_width(self.n, self.p, self.buckets()));
+        let end = self.offset(buckets).div_ceil(8).checked_mul(8);
+        if self.offset(0) != 0 || end != Some(self.data.len()) {
+            return Err(Error::Header);
+        }
+
+        // Check every offset before slicing the data with any of them
+        for bucket in 0..buckets {
+            if self.offset(bucket + 1) < self.offset(bucket)
+                || self.offset(bucket + 1) > self.data.len()
+            {
+                return Err(Error::Header);
+            }
+        }
+
+        for bucket in 0..buckets {
+            let (start, end) = (self.offset(bucket), self.offset(bucket + 1));
+            let limit = (bucket + 1)
+                .saturating_mul(width)
+                .min(range(self.n as u64, self.p));
+            let mut bits = self.data[start..end].iter();
+            let mut last = bucket * width;
+            while bits.len() > 0 {
+                last = last
+                    .checked_add(golomb_decode(&mut bits, self.p)?)
+                    .ok_or(Error::Decode)?;
+                if last >= limit {
+                    return Err(Error::Decode);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the width of the range of values in each bucket.
+fn bucket_width(n: usize, p: u8, buckets: u64) -> u64 {
+    range(n as u64, p).div_ceil(buckets).max(1)
+}
+
+fn read_len<R: Read>(reader: &mut R, len: u64) -> Result<Vec<u8>, Error> {
+    let mut buf = Vec::new();
+    reader.take(len).read_to_end(&mut buf)?;
+    if buf.len() as u64 != len {
+        return Err(Error::Header);
+    }
+    Ok(buf)
+}
+
+impl<D: Digest> ApproximateSet for BucketedGcs<D> {
+    fn contains(&self, input: &[u8]) -> bool {
+        BucketedGcs::contains(self, input)
+    }
+
+    fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    fn false_positive_rate(&self) -> f64 {
+        values_false_positive_rate(self.n, self.p, self.iter())
+    }
+
+    fn iter_hashes(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        Box::new(self.iter())
+    }
+}
@@ -0,0 +1,337 @@
+//! C interface to the library, enabled by the `capi` feature.
+//!
+//! Sets are accessed through opaque [`GcsHandle`] pointers, which start out
+//! unpacked when created with [`gcs_new`] and become packed after calling
+//! [`gcs_pack`]. Handles are serialized to and from the canonical encoding, see
+//! [`Gcs::write_canonical`]. The C declarations are in `include/golomb_set.h`.
+//!
+//! Panics are caught at the boundary of every function rather than unwinding
+//! into C. To build a static or shared library for linking from C, pass the
+//! crate type to `cargo rustc`:
+//!
+//! ```text
+//! cargo rustc --release --lib --features capi --crate-type cdylib
+//! cargo rustc --release --lib --features capi --crate-type staticlib
+//! ```
+
+use {
+    crate::{check_parameters, Error, Gcs, UnpackedGcs},
+    digest::Digest,
+    md5::Md5,
+    sha1::Sha1,
+    std::{
+        panic::{self, AssertUnwindSafe},
+        ptr, slice,
+    },
+    twox_hash::XxHash,
+};
+
+/// The digest used to hash items inserted into and queried from a set.
+///
+/// Functions take the digest as a `uint32_t` rather than this enum, as C
+/// allows any integer to be passed for an enum, and unknown values are
+/// rejected.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GcsDigest {
+    /// MD5, truncated to its first 64 bits.
+    Md5 = 0,
+    /// SHA-1, truncated to its first 64 bits.
+    Sha1 = 1,
+    /// 64 bit xxHash with a seed of 0.
+    XxHash = 2,
+}
+
+impl GcsDigest {
+    /// Returns the digest with the given value, if there is one.
+    fn from_u32(digest: u32) -> Option<Self> {
+        match digest {
+            0 => Some(GcsDigest::Md5),
+            1 => Some(GcsDigest::Sha1),
+            2 => Some(GcsDigest::XxHash),
+            _ => None,
+        }
+    }
+}
+
+/// The result of a fallible function.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GcsStatus {
+    /// The function succeeded.
+    Ok,
+    /// A required pointer argument was null.
+    NullPointer,
+    /// An item was inserted into a set which has been packed.
+    Packed,
+    /// An item was inserted into a set which already contains `n` items.
+    LimitReached,
+    /// Data passed to `gcs_from_bytes` was not a valid canonical encoding.
+    InvalidData,
+    /// The library panicked, which is a bug.
+    Panic,
+    /// A digest argument was not one of the values of `GcsDigest`.
+    InvalidDigest,
+}
+
+impl From<Error> for GcsStatus {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::LimitReached => GcsStatus::LimitReached,
+            _ => GcsStatus::InvalidData,
+        }
+    }
+}
+
+/// An opaque handle to a packed or unpacked set.
+pub struct GcsHandle(Box<dyn Filter>);
+
+/// The operations on a set which are independent of its digest.
+trait Filter {
+    fn insert(&mut self, input: &[u8]) -> Result<(), GcsStatus>;
+    fn pack(&mut self);
+    fn contains(&self, input: &[u8]) -> bool;
+    fn to_bytes(&self) -> Vec<u8>;
+}
+
+enum State<D: Digest> {
+    Unpacked(UnpackedGcs<D>),
+    Packed(Gcs<D>),
+}
+
+impl<D: Digest> Filter for State<D> {
+    fn insert(&mut self, input: &[u8]) -> Result<(), GcsStatus> {
+        match self {
+            State::Unpacked(gcs) => gcs.insert(input).map(|_| ()).map_err(Into::into),
+            State::Packed(_) => Err(GcsStatus::Packed),
+        }
+    }
+
+    fn pack(&mut self) {
+        if let State::Unpacked(gcs) = self {
+            *self = State::Packed(gcs.pack());
+        }
+    }
+
+    fn contains(&self, input: &[u8]) -> bool {
+        match self {
+            State::Unpacked(gcs) => gcs.contains(input),
+            State::Packed(gcs) => gcs.contains(input),
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let result = match self {
+            State::Unpacked(gcs) => gcs.pack().write_canonical(&mut buf),
+            State::Packed(gcs) => gcs.write_canonical(&mut buf),
+        };
+        result.expect("Writing to a Vec failed");
+        buf
+    }
+}
+
+impl GcsHandle {
+    fn new<D: Digest + 'static>(state: State<D>) -> *mut Self {
+        Box::into_raw(Box::new(GcsHandle(Box::new(state))))
+    }
+}
+
+/// Calls `f`, returning `default` instead if it panics.
+fn catch<T>(default: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(default)
+}
+
+/// Returns the slice described by a pointer and length, allowing a null
+/// pointer for an empty slice.
+unsafe fn input<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    if data.is_null() {
+        if len == 0 {
+            Some(&[])
+        } else {
+            None
+        }
+    } else {
+        Some(slice::from_raw_parts(data, len))
+    }
+}
+
+/// Creates a new unpacked set from `n` and `p`, see
+/// `UnpackedGcs::new`, hashing items with one of the values of `GcsDigest`.
+///
+/// Returns null if `digest` is unknown, `n` is 0, `p` is 0 or `n * 2^p` does
+/// not fit in 64 bits. Otherwise the returned handle must be freed with
+/// `gcs_free`.
+#[no_mangle]
+pub extern "C" fn gcs_new(digest: u32, n: usize, p: u8) -> *mut GcsHandle {
+    let digest = match GcsDigest::from_u32(digest) {
+        Some(digest) => digest,
+        None => return ptr::null_mut(),
+    };
+    if check_parameters(n as u64, p).is_err() {
+        return ptr::null_mut();
+    }
+
+    catch(ptr::null_mut(), || match digest {
+        GcsDigest::Md5 => GcsHandle::new(State::Unpacked(UnpackedGcs::<Md5>::new(n, p))),
+        GcsDigest::Sha1 => GcsHandle::new(State::Unpacked(UnpackedGcs::<Sha1>::new(n, p))),
+        GcsDigest::XxHash => GcsHandle::new(State::Unpacked(UnpackedGcs::<XxHash>::new(n, p))),
+    })
+}
+
+/// Adds the `len` bytes at `data` to an unpacked set.
+///
+/// # Errors
+/// * `GcsStatus::Packed` if the set has been packed.
+/// * `GcsStatus::LimitReached` if the set already contains `n` items.
+/// * `GcsStatus::Panic` if the library panics.
+///
+/// # Safety
+/// `gcs` must be a valid handle or null, and `data` must point to `len` readable bytes
+/// or be null if `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn gcs_insert(gcs: *mut GcsHandle, data: *const u8, len: usize) -> GcsStatus {
+    catch(GcsStatus::Panic, || {
+        match (gcs.as_mut(), input(data, len)) {
+            (Some(gcs), Some(input)) => match gcs.0.insert(input) {
+                Ok(()) => GcsStatus::Ok,
+                Err(status) => status,
+            },
+            _ => GcsStatus::NullPointer,
+        }
+    })
+}
+
+/// Packs a set in place, after which no more items can be inserted. Packing an
+/// already packed set does nothing.
+///
+/// # Errors
+/// * `GcsStatus::Panic` if the library panics.
+///
+/// # Safety
+/// `gcs` must be a valid handle or null.
+#[no_mangle]
+pub unsafe extern "C" fn gcs_pack(gcs: *mut GcsHandle) -> GcsStatus {
+    catch(GcsStatus::Panic, || match gcs.as_mut() {
+        Some(gcs) => {
+            gcs.0.pack();
+            GcsStatus::Ok
+        }
+        None => GcsStatus::NullPointer,
+    })
+}
+
+/// Returns whether or not the `len` bytes at `data` are contained in a packed
+/// or unpacked set. If false the input is definitely not present, if true the
+/// input is probably present. Returns false if `gcs` is null or the library
+/// panics.
+///
+/// # Safety
+/// `gcs` must be a valid handle or null, and `data` must point to `len`
+/// readable bytes or be null if `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn gcs_contains(gcs: *const GcsHandle, data: *const u8, len: usize) -> bool {
+    catch(false, || match (gcs.as_ref(), input(data, len)) {
+        (Some(gcs), Some(input)) => gcs.0.contains(input),
+        _ => false,
+    })
+}
+
+/// Reads a packed set from the `len` bytes of its canonical encoding at
+/// `data`, storing its handle in `out`. Items are hashed with one of the values
+/// of `GcsDigest`.
+///
+/// The handle must be freed with `gcs_free`.
+///
+/// # Errors
+/// * `GcsStatus::InvalidDigest` if `digest` is unknown, in which case `out`
+///   is set to null.
+/// * `GcsStatus::InvalidData` if the data is not a valid canonical encoding,
+///   in which case `out` is set to null.
+/// * `GcsStatus::Panic` if the library panics.
+///
+/// # Safety
+/// `data` must point to `len` readable bytes, and `out` must be valid for
+/// writing a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn gcs_from_bytes(
+    digest: u32,
+    data: *const u8,
+    len: usize,
+    out: *mut *mut GcsHandle,
+) -> GcsStatus {
+    catch(GcsStatus::Panic, || from_bytes(digest, data, len, out))
+}
+
+/// Implements `gcs_from_bytes` within `catch`.
+unsafe fn from_bytes(
+    digest: u32,
+    data: *const u8,
+    len: usize,
+    out: *mut *mut GcsHandle,
+) -> GcsStatus {
+    let (mut input, out) = match (input(data, len), out.as_mut()) {
+        (Some(input), Some(out)) => (input, out),
+        _ => return GcsStatus::NullPointer,
+    };
+    *out = ptr::null_mut();
+
+    let digest = match GcsDigest::from_u32(digest) {
+        Some(digest) => digest,
+        None => return GcsStatus::InvalidDigest,
+    };
+    let result = match digest {
+        GcsDigest::Md5 => Gcs::<Md5>::from_canonical_reader(&mut input)
+            .map(|gcs| GcsHandle::new(State::Packed(gcs))),
+        GcsDigest::Sha1 => Gcs::<Sha1>::from_canonical_reader(&mut input)
+            .map(|gcs| GcsHandle::new(State::Packed(gcs))),
+        GcsDigest::XxHash => Gcs::<XxHash>::from_canonical_reader(&mut input)
+            .map(|gcs| GcsHandle::new(State::Packed(gcs))),
+    };
+
+    match result {
+        Ok(gcs) => {
+            *out = gcs;
+            GcsStatus::Ok
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Writes the canonical encoding of a packed or unpacked set to the buffer of
+/// `len` bytes at `buf`, returning the length of the encoding.
+///
+/// Nothing is written if the encoding is longer than `len`, so the required
+/// length can be found by passing a null `buf` and a `len` of 0. Returns 0 if
+/// `gcs` is null or the library panics.
+///
+/// # Safety
+/// `gcs` must be a valid handle or null, and `buf` must point to `len` writable
+/// bytes or be null if `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn gcs_to_bytes(gcs: *const GcsHandle, buf: *mut u8, len: usize) -> usize {
+    catch(0, || {
+        let bytes = match gcs.as_ref() {
+            Some(gcs) => gcs.0.to_bytes(),
+            None => return 0,
+        };
+
+        if bytes.len() <= len && !buf.is_null() {
+            ptr::copy_nonoverlapping(bytes.as_ptr(), buf, bytes.len());
+        }
+        bytes.len()
+    })
+}
+
+/// Frees a set. Freeing a null handle does nothing.
+///
+/// # Safety
+/// `gcs` must be a valid handle or null, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn gcs_free(gcs: *mut GcsHandle) {
+    catch((), || {
+        if !gcs.is_null() {
+            drop(Box::from_raw(gcs));
+        }
+    })
+}
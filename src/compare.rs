@@ -0,0 +1,135 @@
+//! Comparison of the members of packed sets.
+
+use {
+    crate::{range, Error, Gcs, Iter},
+    digest::Digest,
+    std::{cmp::Ordering, iter::Peekable},
+};
+
+impl<D: Digest> Gcs<D> {
+    /// Returns whether or not every value in the set is also in `other`. If
+    /// false the set is definitely not a subset, if true it probably is.
+    ///
+    /// # Errors
+    /// * If the sets have a different `n` or `p`.
+    pub fn is_subset(&self, other: &Self) -> Result<bool, Error> {
+        Ok(self.overlap(other)?.left == 0)
+    }
+
+    /// Returns whether or not the set has no values in common with `other`. If
+    /// true the sets are definitely disjoint, if false they probably are not.
+    ///
+    /// # Errors
+    /// * If the sets have a different `n` or `p`.
+    pub fn is_disjoint(&self, other: &Self) -> Result<bool, Error> {
+        Ok(self.overlap(other)?.both == 0)
+    }
+
+    /// Returns the number of distinct values in both the set and `other`,
+    /// including those of different items whose hashes collided.
+    ///
+    /// # Errors
+    /// * If the sets have a different `n` or `p`.
+    pub fn intersection_len(&self, other: &Self) -> Result<usize, Error> {
+        Ok(self.overlap(other)?.both)
+    }
+
+    /// Returns an estimate of the Jaccard similarity of the items in the set
+    /// and `other`, the size of their intersection divided by the size of
+    /// their union.
+    ///
+    /// Collisions between the hashes of different items make the sets of
+    /// values smaller than the sets of items, and their intersection larger,
+    /// so the number of items in each set and in their union is estimated from
+    /// the number of distinct values given `n` and `p`. Two empty sets have a
+    /// similarity of 1.
+    ///
+    /// # Errors
+    /// * If the sets have a different `n` or `p`.
+    pub fn jaccard(&self, other: &Self) -> Result<f64, Error> {
+        let overlap = self.overlap(other)?;
+        let union = overlap.left + overlap.right + overlap.both;
+        if union == 0 {
+            return Ok(1.0);
+        }
+
+        // `k` uniformly hashed items are expected to have `range * (1 -
+        // e^(-k / range))` distinct values, so invert that for each count. If
+        // every value is present, the number of items is at least `range`.
+        let range = range(self.n as u64, self.p) as f64;
+        let items = |values: usize| {
+            let values = (values as f64).min(range - 1.0);
+            -range * (-values / range).ln_1p()
+        };
+        let (a, b) = (
+            items(overlap.left + overlap.both),
+            items(overlap.right + overlap.both),
+        );
+        let union = items(union);
+        let intersection = (a + b - union).max(0.0).min(a.min(b));
+
+        Ok(intersection / union)
+    }
+
+    /// Counts the distinct values in either or both of the sets, by merging
+    /// their values.
+    fn overlap(&self, other: &Self) -> Result<Overlap, Error> {
+        if self.n != other.n || self.p != other.p {
+            return Err(Error::Parameters);
+        }
+
+        let mut left = Distinct(self.iter().peekable()).peekable();
+        let mut right = Distinct(other.iter().peekable()).peekable();
+        let mut overlap = Overlap::default();
+
+        loop {
+            let ordering = match (left.peek(), right.peek()) {
+                (Some(l), Some(r)) => l.cmp(r),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => return Ok(overlap),
+            };
+
+            match ordering {
+                Ordering::Less => {
+                    overlap.left += 1;
+                    left.next();
+                }
+                Ordering::Greater => {
+                    overlap.right += 1;
+                    right.next();
+                }
+                Ordering::Equal => {
+                    overlap.both += 1;
+                    left.next();
+                    right.next();
+                }
+            }
+        }
+    }
+}
+
+/// The numbers of distinct values in only the left set, only the right set or
+/// both sets.
+#[derive(Default)]
+struct Overlap {
+    left: usize,
+    right: usize,
+    both: usize,
+}
+
+/// An iterator over the values of a set, skipping duplicates.
+struct Distinct<'a>(Peekable<Iter<'a>>);
+
+impl<'a> Iterator for Distinct<'a> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        let value = self.0.next()?;
+        while self.0.peek() == Some(&value) {
+            self.0.next();
+        }
+
+        Some(value)
+    }
+}
@@ -0,0 +1,443 @@
+//! Sets whose hash algorithm is selected at runtime, enabled by the `dynamic`
+//! feature.
+
+use {
+    crate::{
+        check_parameters, digest_hash, reduce, ApproximateSet, Error, Gcs, Iter, Stats, UnpackedGcs,
+    },
+    byteorder::{BigEndian, ReadBytesExt, WriteBytesExt},
+    digest::{
+        generic_array::{typenum::U0, GenericArray},
+        Digest, FixedOutput, Input, Reset,
+    },
+    std::{
+        convert::TryFrom,
+        fmt,
+        hash::Hasher,
+        io::{Read, Write},
+        marker::PhantomData,
+        str::FromStr,
+        sync::RwLock,
+    },
+};
+
+/// Custom hash algorithms which can be looked up by name.
+static REGISTRY: RwLock<Vec<CustomHash>> = RwLock::new(Vec::new());
+
+/// A hash algorithm selected at runtime.
+///
+/// Each algorithm produces a 64 bit hash of an input, which is reduced into
+/// the range of a set's values. For the algorithms with a corresponding
+/// [`Digest`], the hash is the same one used by `Gcs<D>`, so sets can be
+/// converted between the two, see [`DigestAlgorithm`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    /// MD5, truncated to its first 64 bits.
+    Md5,
+    /// SHA-1, truncated to its first 64 bits.
+    Sha1,
+    /// SHA-256, truncated to its first 64 bits.
+    Sha256,
+    /// 64 bit xxHash with a seed of 0.
+    XxHash64,
+    /// SipHash-2-4 with a key of 0.
+    SipHash,
+    /// The 32 bit CRC used by zlib and Ethernet, which is only suitable for
+    /// sets where `n * 2^p` is at most `2^32`.
+    Crc32,
+    /// A custom hash function, created with [`HashAlgorithm::custom`].
+    Custom(CustomHash),
+}
+
+/// A named custom hash function, see [`HashAlgorithm::custom`].
+#[derive(Clone, Copy, Debug)]
+pub struct CustomHash {
+    name: &'static str,
+    hash: fn(&[u8]) -> u64,
+}
+
+impl PartialEq for CustomHash {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for CustomHash {}
+
+/// The algorithms which are always known by name.
+const BUILT_IN: [HashAlgorithm; 6] = [
+    HashAlgorithm::Md5,
+    HashAlgorithm::Sha1,
+    HashAlgorithm::Sha256,
+    HashAlgorithm::XxHash64,
+    HashAlgorithm::SipHash,
+    HashAlgorithm::Crc32,
+];
+
+impl HashAlgorithm {
+    /// Creates a custom hash algorithm from a name and a function returning
+    /// the 64 bit hash of an input.
+    ///
+    /// Custom algorithms are compared by name. To allow them to be parsed
+    /// from their name, see [`register`](HashAlgorithm::register).
+    pub const fn custom(name: &'static str, hash: fn(&[u8]) -> u64) -> Self {
+        HashAlgorithm::Custom(CustomHash { name, hash })
+    }
+
+    /// Registers a custom hash algorithm, allowing it to be parsed from its
+    /// name. Registering an algorithm more than once does nothing.
+    ///
+    /// # Panics
+    /// * Panics if the algorithm is not custom.
+    /// * Panics if the name is already used by a different algorithm.
+    pub fn register(self) {
+        let custom = match self {
+            HashAlgorithm::Custom(custom) => custom,
+            _ => panic!("only custom hash algorithms can be registered"),
+        };
+        assert!(
+            BUILT_IN
+                .iter()
+                .all(|algorithm| algorithm.name() != custom.name),
+            "hash algorithm `{}` is built in",
+            custom.name
+        );
+
+        let mut registry = REGISTRY.write().expect("Hash registry poisoned");
+        match registry.iter().find(|other| other.name == custom.name) {
+            Some(other) => assert!(
+                other.hash as usize == custom.hash as usize,
+                "hash algorithm `{}` is already registered",
+                custom.name
+            ),
+            None => registry.push(custom),
+        }
+    }
+
+    /// Returns the name of the algorithm, as parsed by `from_str`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Sha1 => "sha1",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::XxHash64 => "xxhash64",
+            HashAlgorithm::SipHash => "siphash",
+            HashAlgorithm::Crc32 => "crc32",
+            HashAlgorithm::Custom(custom) => custom.name,
+        }
+    }
+
+    /// Returns the 64 bit hash of an input.
+    pub fn hash(&self, input: &[u8]) -> u64 {
+        match self {
+            HashAlgorithm::Md5 => digest_hash::<md5::Md5>(input),
+            HashAlgorithm::Sha1 => digest_hash::<sha1::Sha1>(input),
+            HashAlgorithm::Sha256 => digest_hash::<sha2::Sha256>(input),
+            HashAlgorithm::XxHash64 => digest_hash::<twox_hash::XxHash64>(input),
+            HashAlgorithm::SipHash => {
+                let mut hasher = siphasher::sip::SipHasher::new();
+                hasher.write(input);
+                hasher.finish()
+            }
+            HashAlgorithm::Crc32 => u64::from(crc32fast::hash(input)),
+            HashAlgorithm::Custom(custom) => (custom.hash)(input),
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        if let Some(&algorithm) = BUILT_IN.iter().find(|algorithm| algorithm.name() == s) {
+            return Ok(algorithm);
+        }
+
+        REGISTRY
+            .read()
+            .expect("Hash registry poisoned")
+            .iter()
+            .find(|custom| custom.name == s)
+            .map(|&custom| HashAlgorithm::Custom(custom))
+            .ok_or(Error::Algorithm)
+    }
+}
+
+/// A [`Digest`] with a corresponding [`HashAlgorithm`], allowing a `Gcs<D>` to
+/// be converted to and from a [`DynGcs`].
+pub trait DigestAlgorithm: Digest {
+    /// The algorithm whose [`hash`](HashAlgorithm::hash) is the same as the
+    /// one used by `Gcs<Self>`.
+    const ALGORITHM: HashAlgorithm;
+}
+
+impl DigestAlgorithm for md5::Md5 {
+    const ALGORITHM: HashAlgorithm = HashAlgorithm::Md5;
+}
+
+impl DigestAlgorithm for sha1::Sha1 {
+    const ALGORITHM: HashAlgorithm = HashAlgorithm::Sha1;
+}
+
+impl DigestAlgorithm for sha2::Sha256 {
+    const ALGORITHM: HashAlgorithm = HashAlgorithm::Sha256;
+}
+
+impl DigestAlgorithm for twox_hash::XxHash64 {
+    const ALGORITHM: HashAlgorithm = HashAlgorithm::XxHash64;
+}
+
+/// A packed Golomb Coded Set whose hash algorithm is selected at runtime.
+///
+/// Apart from hashing inputs with its [`HashAlgorithm`], a `DynGcs` behaves
+/// like a [`Gcs`]. Its encodings are those of a [`Gcs`], preceded by the name
+/// of the algorithm:
+///
+/// | Length    | Content                                                |
+/// |-----------|--------------------------------------------------------|
+/// | 2 bytes   | The length of the name `l`, as a big endian integer    |
+/// | `l` bytes | The name of the algorithm, see [`HashAlgorithm::name`] |
+/// | rest      | The encoding of the set                                |
+///
+/// Custom algorithms must be [registered](HashAlgorithm::register) to be
+/// read.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DynGcs {
+    algorithm: HashAlgorithm,
+    gcs: Gcs<Unhashed>,
+}
+
+impl DynGcs {
+    /// Creates a `DynGcs` from `n` and `p` containing the hashes of the
+    /// inputs, ignoring duplicates.
+    ///
+    /// # Errors
+    /// * If `n` or `p` is 0, or `n * 2^p` does not fit in 64 bits.
+    /// * If more than `n` distinct items are given.
+    pub fn from_inputs<I, A>(
+        algorithm: HashAlgorithm,
+        n: usize,
+        p: u8,
+        inputs: I,
+    ) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = A>,
+        A: AsRef<[u8]>,
+    {
+        check_parameters(n as u64, p)?;
+
+        let mut values = inputs
+            .into_iter()
+            .map(|input| reduce(n as u64, p, algorithm.hash(input.as_ref())))
+            .collect::<Vec<_>>();
+        values.sort_unstable();
+
+        let mut gcs = UnpackedGcs::new(n, p);
+        gcs.merge_sorted(values)?;
+
+        Ok(Self {
+            algorithm,
+            gcs: gcs.pack(),
+        })
+    }
+
+    /// Read a `DynGcs` from any Reader, as written by
+    /// [`write`](DynGcs::write). See [`Gcs::from_counted_reader`].
+    ///
+    /// # Errors
+    /// * If there is an error reading data from `reader`.
+    /// * If the algorithm is neither built in nor registered, see
+    ///   [`Error::Algorithm`].
+    /// * If `n` or `p` are invalid, see [`Error::Parameters`].
+    /// * If the data does not contain the number of values given in its
+    ///   header.
+    pub fn from_reader<R: Read>(reader: &mut R, n: usize, p: u8) -> Result<Self, Error> {
+        Ok(Self {
+            algorithm: read_algorithm(reader)?,
+            gcs: Gcs::from_counted_reader(reader, n, p)?,
+        })
+    }
+
+    /// Read a `DynGcs` in its canonical encoding from any Reader, as written
+    /// by [`write_canonical`](DynGcs::write_canonical). See
+    /// [`Gcs::from_canonical_reader`].
+    ///
+    /// # Errors
+    /// * If there is an error reading data from `reader`.
+    /// * If the algorithm is neither built in nor registered, see
+    ///   [`Error::Algorithm`].
+    /// * If the header is missing or malformed.
+    /// * If `n` or `p` are invalid, see [`Error::Parameters`].
+    /// * If the data is not a valid Golomb-Rice encoding.
+    pub fn from_canonical_reader<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        Ok(Self {
+            algorithm: read_algorithm(reader)?,
+            gcs: Gcs::from_canonical_reader(reader)?,
+        })
+    }
+
+    /// Writes a `DynGcs` to a Writer, see [`Gcs::write_counted`].
+    ///
+    /// # Errors
+    /// * If there is an error writing data to `writer`.
+    /// * If the name of the algorithm is longer than 65535 bytes.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        write_algorithm(writer, self.algorithm)?;
+        self.gcs.write_counted(writer)
+    }
+
+    /// Writes a `DynGcs` in its canonical encoding to a Writer, see
+    /// [`Gcs::write_canonical`].
+    ///
+    /// # Errors
+    /// * If there is an error writing data to `writer`.
+    /// * If the name of the algorithm is longer than 65535 bytes.
+    pub fn write_canonical<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        write_algorithm(writer, self.algorithm)?;
+        self.gcs.write_canonical(writer)
+    }
+
+    /// Returns the hash algorithm of the set.
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// Returns the maximum number of items `n` which the set was created for.
+    pub fn n(&self) -> usize {
+        self.gcs.n
+    }
+
+    /// Returns `p`, where `1 / 2^p` is the probability of a false positive
+    /// once the set is full.
+    pub fn p(&self) -> u8 {
+        self.gcs.p
+    }
+
+    /// Returns whether or not an input is contained in the set. If false the
+    /// input is definitely not present, if true the input is probably present.
+    pub fn contains<A: AsRef<[u8]>>(&self, input: A) -> bool {
+        self.gcs.contains_reduced(self.value(input.as_ref()))
+    }
+
+    /// Returns the number of times an input has been inserted into the set,
+    /// see [`Gcs::count`].
+    pub fn count<A: AsRef<[u8]>>(&self, input: A) -> usize {
+        self.gcs.count_value(self.value(input.as_ref()))
+    }
+
+    /// Returns an iterator over the hashed values stored in the set, in
+    /// ascending order.
+    pub fn iter(&self) -> Iter<'_> {
+        self.gcs.iter()
+    }
+
+    /// Returns statistics describing the contents and encoding of the set.
+    pub fn stats(&self) -> Stats {
+        self.gcs.stats()
+    }
+
+    fn value(&self, input: &[u8]) -> u64 {
+        reduce(self.gcs.n as u64, self.gcs.p, self.algorithm.hash(input))
+    }
+}
+
+impl ApproximateSet for DynGcs {
+    fn contains(&self, input: &[u8]) -> bool {
+        DynGcs::contains(self, input)
+    }
+
+    fn len(&self) -> usize {
+        self.gcs.len()
+    }
+
+    fn false_positive_rate(&self) -> f64 {
+        self.gcs.false_positive_rate()
+    }
+
+    fn iter_hashes(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        self.gcs.iter_hashes()
+    }
+}
+
+impl<D: DigestAlgorithm> From<Gcs<D>> for DynGcs {
+    fn from(gcs: Gcs<D>) -> Self {
+        Self {
+            algorithm: D::ALGORITHM,
+            gcs: cast(gcs),
+        }
+    }
+}
+
+impl<D: DigestAlgorithm> TryFrom<DynGcs> for Gcs<D> {
+    type Error = Error;
+
+    /// Converts a `DynGcs` into a `Gcs<D>`.
+    ///
+    /// # Errors
+    /// * If the algorithm of the set is not `D::ALGORITHM`.
+    fn try_from(gcs: DynGcs) -> Result<Self, Error> {
+        if gcs.algorithm == D::ALGORITHM {
+            Ok(cast(gcs.gcs))
+        } else {
+            Err(Error::Algorithm)
+        }
+    }
+}
+
+/// Reads the name of an algorithm, looking it up with `from_str`.
+fn read_algorithm<R: Read>(reader: &mut R) -> Result<HashAlgorithm, Error> {
+    let len = reader.read_u16::<BigEndian>()?;
+    let mut name = vec![0; usize::from(len)];
+    reader.read_exact(&mut name)?;
+
+    String::from_utf8(name)
+        .map_err(|_| Error::Algorithm)?
+        .parse()
+}
+
+/// Writes the name of an algorithm, prefixed by its length.
+fn write_algorithm<W: Write>(writer: &mut W, algorithm: HashAlgorithm) -> Result<(), Error> {
+    let name = algorithm.name();
+    let len = u16::try_from(name.len()).map_err(|_| Error::Algorithm)?;
+    writer.write_u16::<BigEndian>(len)?;
+    writer.write_all(name.as_bytes())?;
+    Ok(())
+}
+
+/// Changes the digest of a set, without changing its contents.
+fn cast<D: Digest, E: Digest>(gcs: Gcs<D>) -> Gcs<E> {
+    Gcs {
+        n: gcs.n,
+        p: gcs.p,
+        policy: gcs.policy,
+        data: gcs.data,
+        digest: PhantomData,
+    }
+}
+
+/// The digest of the set stored by a `DynGcs`, which is never used since
+/// values are hashed by its `HashAlgorithm` instead.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Unhashed;
+
+impl Input for Unhashed {
+    fn input<B: AsRef<[u8]>>(&mut self, _: B) {}
+}
+
+impl FixedOutput for Unhashed {
+    type OutputSize = U0;
+
+    fn fixed_result(self) -> GenericArray<u8, U0> {
+        GenericArray::default()
+    }
+}
+
+impl Reset for Unhashed {
+    fn reset(&mut self) {}
+}
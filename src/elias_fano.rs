@@ -0,0 +1,325 @@
+//! Sets stored with Elias-Fano encoding, trading some space for fast queries.
+
+use {
+    crate::{
+        digest_value, range, reduce, stats::values_false_positive_rate, values::Values,
+        ApproximateSet, DuplicatePolicy, Gcs, Lookup, UnpackedGcs,
+    },
+    digest::Digest,
+    std::marker::PhantomData,
+};
+
+/// The number of set or unset bits between samples of the select structure.
+const SAMPLE_RATE: usize = 256;
+
+/// A set storing the same sorted values as an [`UnpackedGcs`] with Elias-Fano
+/// encoding.
+///
+/// Each value is split into its lowest `l` bits, where `l` is about
+/// `log2(n * 2^p / len)`, and the remaining high bits. The low bits are packed
+/// together, while the high bits are stored as a unary encoding of the
+/// differences between them. Values take about `l + 2` bits each, around
+/// `p + 2` for a full set, so slightly more than a packed [`Gcs`]. A select
+/// structure sampling the high bits makes accessing any value and checking
+/// whether the set contains a value take constant time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EliasFanoGcs<D: Digest> {
+    n: usize,
+    p: u8,
+    policy: DuplicatePolicy,
+    len: usize,
+    /// The number of low bits of each value.
+    low_bits: u8,
+    low: Vec<u64>,
+    high: BitArray,
+    digest: PhantomData<D>,
+}
+
+impl<D: Digest> EliasFanoGcs<D> {
+    /// Creates an `EliasFanoGcs` from values in ascending order.
+    fn from_sorted<I>(n: usize, p: u8, policy: DuplicatePolicy, values: I) -> Self
+    where
+        I: ExactSizeIterator<Item = u64>,
+    {
+        let len = values.len();
+        let universe = range(n as u64, p).max(1);
+        let low_bits = 63 - (universe / len.max(1) as u64).max(1).leading_zeros() as u8;
+
+        // Each value sets the bit at its high bits plus its index, and each
+        // possible high bits value is followed by an unset bit
+        let buckets = ((universe - 1) >> low_bits) as usize + 1;
+        let mut high = vec![0u64; (len + buckets).div_ceil(64)];
+        let mut low = vec![0u64; (len * low_bits as usize).div_ceil(64)];
+        for (i, value) in values.enumerate() {
+            let position = (value >> low_bits) as usize + i;
+            high[position / 64] |= 1 << (position % 64);
+            write_low(&mut low, low_bits, i, value);
+        }
+
+        Self {
+            n,
+            p,
+            policy,
+            len,
+            low_bits,
+            low,
+            high: BitArray::new(high, len + buckets),
+            digest: PhantomData,
+        }
+    }
+
+    /// Returns whether or not an input is contained in the set. If false the
+    /// input is definitely not present, if true the input is probably present.
+    pub fn contains<A: AsRef<[u8]>>(&self, input: A) -> bool {
+        self.contains_reduced(digest_value::<D>(self.n as u64, self.p, input.as_ref()))
+    }
+
+    /// Returns whether or not a pre-computed 64 bit hash is contained in the
+    /// set, see [`UnpackedGcs::insert_hash`].
+    pub fn contains_hash(&self, hash: u64) -> bool {
+        self.contains_reduced(reduce(self.n as u64, self.p, hash))
+    }
+
+    /// Returns whether or not a value in the range `[0, n * 2^p)` is contained
+    /// in the set, see [`UnpackedGcs::insert_reduced`].
+    pub fn contains_reduced(&self, value: u64) -> bool {
+        let bucket = (value >> self.low_bits) as usize;
+        if bucket >= self.high.zeros {
+            return false;
+        }
+
+        // The values with the same high bits follow the end of the previous
+        // bucket, which is marked by its unset bit
+        let (mut i, mut position) = match bucket {
+            0 => (0, 0),
+            _ => {
+                let end = self.high.select(bucket - 1, false);
+                (end + 1 - bucket, end + 1)
+            }
+        };
+
+        let low = value & low_mask(self.low_bits);
+        while self.high.get(position) {
+            match read_low(&self.low, self.low_bits, i) {
+                other if other == low => return true,
+                other if other > low => return false,
+                _ => {}
+            }
+            i += 1;
+            position += 1;
+        }
+
+        false
+    }
+
+    /// Returns the value at `index` in ascending order, or `None` if `index`
+    /// is not less than the number of values.
+    pub fn get(&self, index: usize) -> Option<u64> {
+        if index >= self.len {
+            return None;
+        }
+
+        let high = (self.high.select(index, true) - index) as u64;
+        Some(high << self.low_bits | read_low(&self.low, self.low_bits, index))
+    }
+
+    /// Returns an iterator over the values stored in the set, in ascending
+    /// order.
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..self.len).map(move |i| self.get(i).expect("Index is within the set"))
+    }
+
+    /// Returns the number of values in the set.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether or not the set contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the maximum number of items `n` which the set was created for.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Returns `p`, where `1/2^p` is the probability of a false positive when
+    /// the set is full.
+    pub fn p(&self) -> u8 {
+        self.p
+    }
+
+    /// Returns the policy used for handling duplicate insertions.
+    pub fn policy(&self) -> DuplicatePolicy {
+        self.policy
+    }
+
+    /// Returns the number of bits used to store the values, excluding the
+    /// select structure.
+    pub fn bits(&self) -> usize {
+        self.len * self.low_bits as usize + self.high.len
+    }
+
+    /// Converts the set into an `UnpackedGcs`.
+    pub fn unpack(&self) -> UnpackedGcs<D> {
+        UnpackedGcs {
+            n: self.n,
+            p: self.p,
+            policy: self.policy,
+            lookup: Lookup::default(),
+            values: Values::from_vec(self.n, self.p, self.iter().collect()),
+            digest: PhantomData,
+        }
+    }
+
+    /// Converts the set into a packed `Gcs`.
+    pub fn pack(&self) -> Gcs<D> {
+        self.unpack().pack()
+    }
+}
+
+impl<D: Digest> From<&UnpackedGcs<D>> for EliasFanoGcs<D> {
+    fn from(gcs: &UnpackedGcs<D>) -> Self {
+        Self::from_sorted(gcs.n, gcs.p, gcs.policy, gcs.values.iter())
+    }
+}
+
+impl<D: Digest> From<&Gcs<D>> for EliasFanoGcs<D> {
+    fn from(gcs: &Gcs<D>) -> Self {
+        let values = gcs.iter().collect::<Vec<_>>();
+        Self::from_sorted(gcs.n, gcs.p, gcs.policy, values.into_iter())
+    }
+}
+
+/// A sequence of bits with a select structure.
+#[derive(Clone, Debug, PartialEq)]
+struct BitArray {
+    words: Vec<u64>,
+    len: usize,
+    zeros: usize,
+    /// The word containing every `SAMPLE_RATE`th set bit, and the number of
+    /// set bits before it.
+    ones_samples: Vec<(usize, usize)>,
+    /// The word containing every `SAMPLE_RATE`th unset bit, and the number of
+    /// unset bits before it.
+    zeros_samples: Vec<(usize, usize)>,
+}
+
+impl BitArray {
+    fn new(words: Vec<u64>, len: usize) -> Self {
+        let mut array = Self {
+            words,
+            len,
+            zeros: 0,
+            ones_samples: Vec::new(),
+            zeros_samples: Vec::new(),
+        };
+
+        let (mut ones, mut zeros) = (0usize, 0usize);
+        for i in 0..array.words.len() {
+            let word_ones = array.word(i, true).count_ones() as usize;
+            let word_zeros = array.word(i, false).count_ones() as usize;
+            // Sample the word containing each multiple of `SAMPLE_RATE`
+            if ones.div_ceil(SAMPLE_RATE) * SAMPLE_RATE < ones + word_ones {
+                array.ones_samples.push((i, ones));
+            }
+            if zeros.div_ceil(SAMPLE_RATE) * SAMPLE_RATE < zeros + word_zeros {
+                array.zeros_samples.push((i, zeros));
+            }
+            ones += word_ones;
+            zeros += word_zeros;
+        }
+        array.zeros = zeros;
+
+        array
+    }
+
+    fn get(&self, position: usize) -> bool {
+        position < self.len && self.words[position / 64] >> (position % 64) & 1 == 1
+    }
+
+    /// Returns the word at index `i` with set bits marking the set or unset
+    /// bits within `len`.
+    fn word(&self, i: usize, ones: bool) -> u64 {
+        let word = if ones { self.words[i] } else { !self.words[i] };
+        match self.len - i * 64 {
+            remaining if remaining < 64 => word & ((1 << remaining) - 1),
+            _ => word,
+        }
+    }
+
+    /// Returns the position of the set or unset bit with the given rank, which
+    /// must exist.
+    fn select(&self, rank: usize, ones: bool) -> usize {
+        let samples = if ones {
+            &self.ones_samples
+        } else {
+            &self.zeros_samples
+        };
+        let (mut i, mut before) = samples[rank / SAMPLE_RATE];
+
+        loop {
+            let mut word = self.word(i, ones);
+            let count = word.count_ones() as usize;
+            if rank < before + count {
+                for _ in 0..rank - before {
+                    word &= word - 1;
+                }
+                return i * 64 + word.trailing_zeros() as usize;
+            }
+            before += count;
+            i += 1;
+        }
+    }
+}
+
+fn low_mask(low_bits: u8) -> u64 {
+    (1u64 << low_bits) - 1
+}
+
+fn read_low(low: &[u64], low_bits: u8, i: usize) -> u64 {
+    if low_bits == 0 {
+        return 0;
+    }
+
+    let start = i * low_bits as usize;
+    let (word, offset) = (start / 64, start % 64);
+    let mut value = low[word] >> offset;
+    if offset + low_bits as usize > 64 {
+        value |= low[word + 1] << (64 - offset);
+    }
+    value & low_mask(low_bits)
+}
+
+fn write_low(low: &mut [u64], low_bits: u8, i: usize, value: u64) {
+    if low_bits == 0 {
+        return;
+    }
+
+    let value = value & low_mask(low_bits);
+    let start = i * low_bits as usize;
+    let (word, offset) = (start / 64, start % 64);
+    low[word] |= value << offset;
+    if offset + low_bits as usize > 64 {
+        low[word + 1] |= value >> (64 - offset);
+    }
+}
+
+impl<D: Digest> ApproximateSet for EliasFanoGcs<D> {
+    fn contains(&self, input: &[u8]) -> bool {
+        EliasFanoGcs::contains(self, input)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn false_positive_rate(&self) -> f64 {
+        values_false_positive_rate(self.n, self.p, self.iter())
+    }
+
+    fn iter_hashes(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        Box::new(self.iter())
+    }
+}
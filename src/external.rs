@@ -0,0 +1,334 @@
+//! Construction of sets which are too large for their values to be held in memory.
+
+use {
+    crate::{digest_value, Error, CANONICAL_MAGIC},
+    byteorder::{BigEndian, ReadBytesExt, WriteBytesExt},
+    digest::Digest,
+    std::{
+        cmp::Reverse,
+        collections::BinaryHeap,
+        env,
+        fs::{self, File},
+        io::{self, BufReader, BufWriter, Write},
+        marker::PhantomData,
+        path::PathBuf,
+        process, slice,
+        sync::atomic::{AtomicUsize, Ordering},
+    },
+};
+
+/// Used to give the runs of each builder in this process unique file names.
+static BUILDER_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// The maximum number of runs which are read at once when merging.
+const MAX_OPEN_RUNS: usize = 64;
+
+/// Builds a packed Golomb Coded Set using a bounded amount of memory.
+///
+/// Hashed values are buffered in memory until the memory limit is reached, at
+/// which point they are sorted and spilled to a temporary file as a run. When
+/// finished, the runs are merged in passes of at most 64 runs into a single
+/// run, which is then read into the Golomb-Rice encoder. Like a set using
+/// [`DuplicatePolicy::Set`](crate::DuplicatePolicy::Set), duplicate values are
+/// only stored once.
+///
+/// Besides the memory limit, merging uses a small read buffer for each of the
+/// runs being merged.
+#[derive(Debug)]
+pub struct ExternalGcsBuilder<D: Digest> {
+    n: usize,
+    p: u8,
+    dir: PathBuf,
+    id: usize,
+    /// The number of runs which have been written, used to name the next one.
+    written: usize,
+    capacity: usize,
+    buffer: Vec<u64>,
+    runs: Vec<Run>,
+    digest: PhantomData<D>,
+}
+
+impl<D: Digest> ExternalGcsBuilder<D> {
+    /// Creates a new `ExternalGcsBuilder` from `n` and `p`, which buffers at
+    /// most `memory_limit` bytes of hashed values before spilling them to the
+    /// system's temporary directory.
+    pub fn new(n: usize, p: u8, memory_limit: usize) -> Self {
+        Self::with_temp_dir(n, p, memory_limit, env::temp_dir())
+    }
+
+    /// Creates a new `ExternalGcsBuilder` from `n` and `p`, which buffers at
+    /// most `memory_limit` bytes of hashed values before spilling them to
+    /// temporary files in `dir`.
+    pub fn with_temp_dir<P: Into<PathBuf>>(n: usize, p: u8, memory_limit: usize, dir: P) -> Self {
+        Self {
+            n,
+            p,
+            dir: dir.into(),
+            id: BUILDER_ID.fetch_add(1, Ordering::Relaxed),
+            written: 0,
+            capacity: (memory_limit / 8).max(1),
+            buffer: Vec::new(),
+            runs: Vec::new(),
+            digest: PhantomData,
+        }
+    }
+
+    /// Adds an entry to the set.
+    ///
+    /// # Errors
+    /// * If there is an error writing a run to a temporary file.
+    pub fn insert<A: AsRef<[u8]>>(&mut self, input: A) -> Result<(), Error> {
+        self.buffer
+            .push(digest_value::<D>(self.n as u64, self.p, input.as_ref()));
+
+        if self.buffer.len() >= self.capacity {
+            self.spill()?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the set in its canonical encoding, see
+    /// [`Gcs::write_canonical`](crate::Gcs::write_canonical), returning the
+    /// number of distinct values written.
+    ///
+    /// The written set can be read with
+    /// [`Gcs::from_canonical_reader`](crate::Gcs::from_canonical_reader).
+    ///
+    /// # Errors
+    /// * If there is an error reading or writing a temporary file.
+    /// * If there is an error writing data to `writer`.
+    /// * If more than `n` distinct items have been inserted, in which case
+    ///   nothing is written.
+    pub fn finish<W: Write>(mut self, writer: &mut W) -> Result<usize, Error> {
+        self.buffer.sort_unstable();
+        self.buffer.dedup();
+
+        // Merge the runs in passes until they can be read at once along with
+        // the buffer, then into a single run, as the number of distinct values
+        // is needed for the header
+        while self.runs.len() >= MAX_OPEN_RUNS {
+            let runs = self.runs.drain(..MAX_OPEN_RUNS).collect::<Vec<_>>();
+            let path = self.next_path();
+            self.runs.push(Run::write(path, merge(&runs, &[])?)?);
+        }
+        let run = if self.runs.is_empty() {
+            None
+        } else {
+            let path = self.next_path();
+            let run = Run::write(path, merge(&self.runs, &self.buffer)?)?;
+            self.runs.clear();
+            self.buffer = Vec::new();
+            Some(run)
+        };
+
+        let len = run.as_ref().map_or(self.buffer.len(), |run| run.len);
+        if len > self.n {
+            return Err(Error::LimitReached);
+        }
+
+        writer.write_all(&CANONICAL_MAGIC)?;
+        writer.write_u8(self.p)?;
+        writer.write_u64::<BigEndian>(self.n as u64)?;
+        writer.write_u64::<BigEndian>(len as u64)?;
+
+        let values = match &run {
+            Some(run) => merge(slice::from_ref(run), &[])?,
+            None => merge(&[], &self.buffer)?,
+        };
+        let mut bits = BitWriter::new(BufWriter::new(writer));
+        let mut last = 0;
+        for value in values {
+            let value = value?;
+            bits.write_golomb(value - last, self.p)?;
+            last = value;
+        }
+        bits.finish()?.flush()?;
+
+        Ok(len)
+    }
+
+    /// Sorts the buffered values and writes them to a new run.
+    fn spill(&mut self) -> Result<(), Error> {
+        self.buffer.sort_unstable();
+        self.buffer.dedup();
+
+        let path = self.next_path();
+        let run = Run::write(path, self.buffer.iter().map(|&value| Ok(value)))?;
+
+        self.runs.push(run);
+        self.buffer.clear();
+        Ok(())
+    }
+
+    /// Returns the path of a new run.
+    fn next_path(&mut self) -> PathBuf {
+        let path = self.dir.join(format!(
+            "golomb-set-{}-{}-{}.run",
+            process::id(),
+            self.id,
+            self.written
+        ));
+        self.written += 1;
+        path
+    }
+}
+
+/// Returns an iterator merging sorted runs and buffered values, skipping
+/// duplicates.
+fn merge<'a>(runs: &[Run], buffer: &'a [u64]) -> Result<Merge<'a>, Error> {
+    let mut sources = Vec::with_capacity(runs.len() + 1);
+    for run in runs {
+        sources.push(Source::Run(BufReader::new(File::open(&run.path)?)));
+    }
+    sources.push(Source::Buffer(buffer.iter()));
+
+    let mut heap = BinaryHeap::with_capacity(sources.len());
+    for (i, source) in sources.iter_mut().enumerate() {
+        if let Some(value) = source.next()? {
+            heap.push(Reverse((value, i)));
+        }
+    }
+
+    Ok(Merge {
+        sources,
+        heap,
+        last: None,
+    })
+}
+
+/// A temporary file containing sorted values, which is deleted when dropped.
+#[derive(Debug)]
+struct Run {
+    path: PathBuf,
+    /// The number of values in the file.
+    len: usize,
+}
+
+impl Run {
+    /// Writes sorted values to a new run at `path`.
+    fn write<I>(path: PathBuf, values: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = io::Result<u64>>,
+    {
+        // Created first so that the file is deleted if writing fails
+        let mut run = Run { path, len: 0 };
+
+        let mut writer = BufWriter::new(File::create(&run.path)?);
+        for value in values {
+            writer.write_u64::<BigEndian>(value?)?;
+            run.len += 1;
+        }
+        writer.flush()?;
+
+        Ok(run)
+    }
+}
+
+impl Drop for Run {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// A sorted source of values being merged.
+enum Source<'a> {
+    Run(BufReader<File>),
+    Buffer(std::slice::Iter<'a, u64>),
+}
+
+impl<'a> Source<'a> {
+    fn next(&mut self) -> io::Result<Option<u64>> {
+        match self {
+            Source::Run(reader) => match reader.read_u64::<BigEndian>() {
+                Ok(value) => Ok(Some(value)),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+                Err(e) => Err(e),
+            },
+            Source::Buffer(iter) => Ok(iter.next().cloned()),
+        }
+    }
+}
+
+/// An iterator over the distinct values of several sorted sources, in
+/// ascending order.
+struct Merge<'a> {
+    sources: Vec<Source<'a>>,
+    heap: BinaryHeap<Reverse<(u64, usize)>>,
+    last: Option<u64>,
+}
+
+impl<'a> Iterator for Merge<'a> {
+    type Item = io::Result<u64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(Reverse((value, i))) = self.heap.pop() {
+            match self.sources[i].next() {
+                Ok(Some(next)) => self.heap.push(Reverse((next, i))),
+                Ok(None) => {}
+                Err(e) => return Some(Err(e)),
+            }
+
+            if self.last.replace(value) != Some(value) {
+                return Some(Ok(value));
+            }
+        }
+
+        None
+    }
+}
+
+/// Writes individual bits to a writer, most significant bit of each byte first.
+struct BitWriter<W: Write> {
+    inner: W,
+    byte: u8,
+    len: u8,
+}
+
+impl<W: Write> BitWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            byte: 0,
+            len: 0,
+        }
+    }
+
+    fn write_bit(&mut self, bit: bool) -> io::Result<()> {
+        self.byte |= (bit as u8) << (7 - self.len);
+        self.len += 1;
+
+        if self.len == 8 {
+            self.inner.write_u8(self.byte)?;
+            self.byte = 0;
+            self.len = 0;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the Golomb-Rice encoding of `n`, with modulus 2^p.
+    fn write_golomb(&mut self, n: u64, p: u8) -> io::Result<()> {
+        let quo = n >> p;
+
+        for _ in 0..quo {
+            self.write_bit(true)?;
+        }
+        self.write_bit(false)?;
+
+        for i in (0..p).rev() {
+            self.write_bit((n >> i) & 1 == 1)?;
+        }
+
+        Ok(())
+    }
+
+    /// Pads the final byte with zero bits, returning the inner writer.
+    fn finish(mut self) -> io::Result<W> {
+        if self.len > 0 {
+            self.inner.write_u8(self.byte)?;
+        }
+
+        Ok(self.inner)
+    }
+}
@@ -0,0 +1,192 @@
+//! Packed sets with an index of buckets, allowing queries to decode a single
+//! bucket.
+
+use {
+    crate::{digest_value, golomb_decode, ApproximateSet, Error, Gcs, Iter},
+    bitvec::prelude::BitVec,
+    byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt},
+    digest::Digest,
+    std::io::{Read, Write},
+};
+
+/// An entry of the index of an [`IndexedGcs`], marking the start of a bucket.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// The value preceding the first value of the bucket, or 0 for the first
+    /// bucket.
+    pub value: u64,
+    /// The offset in bits of the encoding of the first value of the bucket.
+    pub position: u64,
+}
+
+/// A packed Golomb-coded Set along with an index of buckets of a fixed number
+/// of values, allowing queries to only decode a single bucket.
+///
+/// The encoding is specific to this crate, and is not the format of the files
+/// written by rasky's C++ implementation. It consists of the following fields,
+/// where integers are little endian:
+///
+/// | Length         | Content                                              |
+/// |----------------|------------------------------------------------------|
+/// | 8 bytes        | `n`                                                  |
+/// | 8 bytes        | `p`                                                  |
+/// | 8 bytes        | The number of index entries `k`                      |
+/// | `k * 16` bytes | Each index entry's `value` followed by its `position` |
+/// | rest           | The Golomb-Rice encoded values, as written by [`Gcs::write`] |
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexedGcs<D: Digest> {
+    gcs: Gcs<D>,
+    index: Vec<IndexEntry>,
+}
+
+impl<D: Digest> IndexedGcs<D> {
+    /// Creates an `IndexedGcs` from a packed `Gcs`, with buckets of
+    /// `granularity` values.
+    ///
+    /// # Panics
+    /// * Panics if `granularity == 0`.
+    pub fn new(gcs: Gcs<D>, granularity: usize) -> Self {
+        assert!(granularity > 0, "granularity cannot be 0");
+
+        let index = entries(&gcs.data, gcs.p)
+            .step_by(granularity)
+            .collect::<Vec<_>>();
+
+        Self { gcs, index }
+    }
+
+    /// Read an `IndexedGcs` from any Reader.
+    ///
+    /// # Errors
+    /// * If there is an error reading data from `reader`.
+    /// * If the header or index is malformed.
+    /// * If the data is not a valid Golomb-Rice encoding.
+    pub fn from_reader<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        let n = reader.read_u64::<LittleEndian>()? as usize;
+        let p = reader.read_u64::<LittleEndian>()?;
+        if p == 0 || p >= 64 {
+            return Err(Error::Header);
+        }
+        let p = p as u8;
+
+        let len = reader.read_u64::<LittleEndian>()?;
+        let mut index = Vec::new();
+        for _ in 0..len {
+            index.push(IndexEntry {
+                value: reader.read_u64::<LittleEndian>()?,
+                position: reader.read_u64::<LittleEndian>()?,
+            });
+        }
+
+        let gcs = Gcs::from_reader(reader, n, p)?;
+
+        // Every entry must mark the start of a value, in order
+        let valid = {
+            let mut boundaries = entries(&gcs.data, p);
+            index
+                .iter()
+                .all(|entry| boundaries.any(|boundary| boundary == *entry))
+        };
+        if !valid {
+            return Err(Error::Header);
+        }
+
+        Ok(Self { gcs, index })
+    }
+
+    /// Writes an `IndexedGcs` to a Writer.
+    ///
+    /// # Errors
+    /// * If there is an error writing data to `writer`.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        writer.write_u64::<LittleEndian>(self.gcs.n as u64)?;
+        writer.write_u64::<LittleEndian>(u64::from(self.gcs.p))?;
+        writer.write_u64::<LittleEndian>(self.index.len() as u64)?;
+        for entry in &self.index {
+            writer.write_u64::<LittleEndian>(entry.value)?;
+            writer.write_u64::<LittleEndian>(entry.position)?;
+        }
+
+        self.gcs.write(writer)
+    }
+
+    /// Returns whether or not an input is contained in the set. If false the
+    /// input is definitely not present, if true the input is probably present.
+    ///
+    /// Only the bucket which could contain the input is decoded.
+    pub fn contains<A: AsRef<[u8]>>(&self, input: A) -> bool {
+        let input = digest_value::<D>(self.gcs.n as u64, self.gcs.p, input.as_ref());
+
+        // The bucket following the last entry preceding the input
+        let start = match self.index.partition_point(|entry| entry.value < input) {
+            0 => IndexEntry {
+                value: 0,
+                position: 0,
+            },
+            i => self.index[i - 1],
+        };
+
+        let iter = Iter {
+            bits: self.gcs.data[start.position as usize..].iter().peekable(),
+            p: self.gcs.p,
+            last: start.value,
+        };
+
+        iter.take_while(|&value| value <= input)
+            .any(|value| value == input)
+    }
+
+    /// Returns the index of the set.
+    pub fn index(&self) -> &[IndexEntry] {
+        &self.index
+    }
+
+    /// Returns a reference to the packed set.
+    pub fn gcs(&self) -> &Gcs<D> {
+        &self.gcs
+    }
+
+    /// Returns the packed set, discarding the index.
+    pub fn into_gcs(self) -> Gcs<D> {
+        self.gcs
+    }
+}
+
+/// Returns an iterator over the entries marking the start of each value.
+fn entries(data: &BitVec, p: u8) -> impl Iterator<Item = IndexEntry> + '_ {
+    let mut bits = data.iter();
+    let mut last = 0;
+
+    std::iter::from_fn(move || {
+        if bits.len() == 0 {
+            return None;
+        }
+
+        let entry = IndexEntry {
+            value: last,
+            position: (data.len() - bits.len()) as u64,
+        };
+        // This should never happen because data is checked on creation
+        last += golomb_decode(&mut bits, p).expect("Golomb decoding failed");
+
+        Some(entry)
+    })
+}
+
+impl<D: Digest> ApproximateSet for IndexedGcs<D> {
+    fn contains(&self, input: &[u8]) -> bool {
+        IndexedGcs::contains(self, input)
+    }
+
+    fn len(&self) -> usize {
+        self.gcs.len()
+    }
+
+    fn false_positive_rate(&self) -> f64 {
+        self.gcs.false_positive_rate()
+    }
+
+    fn iter_hashes(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        self.gcs.iter_hashes()
+    }
+}
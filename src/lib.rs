@@ -16,6 +16,11 @@
 //! `1 / 2 ^ P` is the desired probability of a false positive when the set is full. If fewer items
 //! have been inserted the real probability will be significantly lower.
 //!
+//! By default a set ignores attempts to insert a value which is already present, so duplicates
+//! neither use up one of the `N` slots nor take up space once packed. A set constructed with
+//! [`DuplicatePolicy::Multiset`] instead keeps every insertion, and the number of times a value was
+//! inserted can be queried with `count`.
+//!
 //! The chosen hashing algorithm must have a uniform distribution (which is not the same as being
 //! cryptograpically secure) and the output length of the hash in bits must be greater than
 //! `log2(N * 2 ^ P)` bits. This is not currently enforced by the library and failing to do so
@@ -49,24 +54,87 @@
 //! assert!(gcs.contains(b"bravo"));
 //! assert!(!gcs.contains(b"charlie"));
 //! ```
+//!
+//! ## Cargo Features
+//!
+//! * `bloom`: Adds the [`bloom`] module, for converting sets into Bloom filters from the `bloom` crate
+//!   and comparing them, and a filter keeping recent inserts in a sorted list in front of a packed set.
+//! * `capi`: Adds the [`capi`] module, a C interface for sets using MD5, SHA-1 or xxHash, declared in
+//!   `include/golomb_set.h`. Build a library for linking from C with
+//!   `cargo rustc --release --lib --features capi --crate-type cdylib`.
+//! * `cli`: Builds the `gcs` command-line tool for building, querying, inspecting and converting
+//!   set files.
+//! * `dynamic`: Adds [`DynGcs`], a packed set whose [`HashAlgorithm`] is selected at runtime.
+//! * `rayon`: Adds methods for inserting items and packing sets in parallel.
+//! * `testing`: Adds the [`testing`] module, containing utilities for measuring the behaviour of
+//!   sets.
+//! * `serde`: Implements `Serialize` and `Deserialize` for `Gcs` and `UnpackedGcs`.
+//! * `async`: Adds methods for reading and writing packed sets with tokio's `AsyncRead` and `AsyncWrite`,
+//!   and for inserting items from a `Stream`.
 
 #![deny(missing_docs)]
+// `failure_derive` generates its impls inside of an anonymous constant
+#![allow(non_local_definitions)]
 
 #[macro_use]
 extern crate failure_derive;
 
+mod approximate;
+#[cfg(feature = "async")]
+mod async_io;
+#[cfg(feature = "bloom")]
+pub mod bloom;
+mod bucketed;
+#[cfg(feature = "capi")]
+pub mod capi;
+mod compare;
+#[cfg(feature = "dynamic")]
+mod dynamic;
+mod elias_fano;
+mod external;
+mod indexed;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod patch;
+pub mod reconcile;
+mod rolling;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod sharded;
+mod stats;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod text;
+mod values;
+
+#[cfg(feature = "dynamic")]
+pub use dynamic::{CustomHash, DigestAlgorithm, DynGcs, HashAlgorithm};
+pub use {
+    approximate::{ApproximateSet, MutableApproximateSet},
+    bucketed::BucketedGcs,
+    elias_fano::EliasFanoGcs,
+    external::ExternalGcsBuilder,
+    indexed::{IndexEntry, IndexedGcs},
+    patch::GcsPatch,
+    rolling::RollingGcs,
+    sharded::ShardedGcs,
+    stats::Stats,
+};
+
 use {
     bitvec::{
         prelude::{BigEndian, BitVec, LittleEndian},
         store::BitStore,
     },
-    byteorder::ByteOrder,
-    digest::Digest,
+    byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt},
+    digest::{generic_array::GenericArray, Digest},
     num_integer::div_rem,
     std::{
         io::{self, Read, Write},
+        iter::Peekable,
         marker::PhantomData,
     },
+    values::Values,
 };
 
 /// Errors that may occur when handling Golomb Coded Sets.
@@ -80,6 +148,41 @@ pub enum Error {
     /// when unpacking or calling the `contains` method on a a packed GCS.
     #[fail(display = "Decoding failed due to invalid Golomb-Rice bit sequence")]
     Decode,
+    /// The header of an encoded GCS was missing or malformed.
+    #[fail(display = "Invalid or missing header")]
+    Header,
+    /// Text passed to [`Gcs::from_hex`] was not valid hexadecimal.
+    #[fail(display = "Invalid hexadecimal text")]
+    Hex,
+    /// Text passed to [`Gcs::from_base64`] or parsed as a `Gcs` was not valid
+    /// base64.
+    #[fail(display = "Invalid base64 text")]
+    Base64,
+    /// A hash algorithm name was not recognised, or a set was converted to a
+    /// type using a different hash algorithm.
+    #[fail(display = "Unknown or mismatched hash algorithm")]
+    Algorithm,
+    /// A value passed to [`UnpackedGcs::insert_reduced`], or decoded from an
+    /// encoded GCS, was not less than `n * 2^p`.
+    #[fail(display = "Value out of range for `n` and `p`")]
+    OutOfRange,
+    /// Two sets being compared have a different `n` or `p`, or the `n` and `p`
+    /// of an encoded GCS are invalid: `n` and `p` must be greater than 0, and
+    /// `n * 2^p` must fit in 64 bits.
+    #[fail(display = "Invalid or mismatched parameters")]
+    Parameters,
+    /// A reconciliation message was received at the wrong point in the
+    /// protocol, see [`reconcile`].
+    #[fail(display = "Unexpected reconciliation message")]
+    Protocol,
+    /// A patch passed to [`Gcs::apply_patch`] removes a value which is not in
+    /// the set.
+    #[fail(display = "Patch does not apply to the set")]
+    Patch,
+    /// The final values of a set passed to [`Gcs::write`] would be read back
+    /// as padding.
+    #[fail(display = "Values cannot be distinguished from padding")]
+    Padding,
     /// todo
     #[fail(display = "IO error: {}", _0)]
     Io(io::Error),
@@ -91,33 +194,115 @@ impl From<io::Error> for Error {
     }
 }
 
+/// Determines how a set handles the insertion of a value which is already present.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DuplicatePolicy {
+    /// Duplicate values are skipped, each value is stored at most once.
+    #[default]
+    Set,
+    /// Every inserted value is stored, allowing the number of insertions of a
+    /// value to be queried with `count`.
+    Multiset,
+}
+
+/// Determines how an [`UnpackedGcs`] searches its sorted values when queried.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Lookup {
+    /// Binary search, taking `log2(len)` steps.
+    #[default]
+    Binary,
+    /// Interpolation search, which predicts the position of a value from its
+    /// size since values are uniformly distributed over `[0, n * 2^p)`. It
+    /// takes about `log2(log2(len))` steps, and falls back to binary search if
+    /// the prediction does not narrow down the position quickly enough.
+    Interpolation,
+}
+
 /// An unpacked Golomb Coded Set.
-#[derive(Clone, Debug, PartialEq)]
+///
+/// Sets are equal if they have the same `n`, `p`, [`DuplicatePolicy`] and
+/// values, regardless of their [`Lookup`].
+#[derive(Clone, Debug)]
 pub struct UnpackedGcs<D: Digest> {
     n: usize,
     p: u8,
-    values: Vec<u64>,
+    policy: DuplicatePolicy,
+    lookup: Lookup,
+    values: Values,
     digest: PhantomData<D>,
 }
 
+impl<D: Digest> PartialEq for UnpackedGcs<D> {
+    fn eq(&self, other: &Self) -> bool {
+        self.n == other.n
+            && self.p == other.p
+            && self.policy == other.policy
+            && self.values == other.values
+    }
+}
+
 impl<D: Digest> UnpackedGcs<D> {
     /// Creates a new `UnpackedGcs` from `n` and `p`, where `1/2^p` is the probability
     /// of a false positive when n items have been inserted into the set.
+    ///
+    /// Duplicate insertions are skipped, see [`DuplicatePolicy::Set`].
     pub fn new(n: usize, p: u8) -> Self {
+        Self::with_policy(n, p, DuplicatePolicy::default())
+    }
+
+    /// Creates a new `UnpackedGcs` from `n` and `p` which handles duplicate
+    /// insertions according to `policy`.
+    pub fn with_policy(n: usize, p: u8, policy: DuplicatePolicy) -> Self {
         Self {
             n,
             p,
-            values: Vec::new(),
+            policy,
+            lookup: Lookup::default(),
+            values: Values::new(n, p),
             digest: PhantomData,
         }
     }
 
+    /// Returns the maximum number of items `n` which the set was created for.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Returns `p`, where `1/2^p` is the probability of a false positive when
+    /// the set is full.
+    pub fn p(&self) -> u8 {
+        self.p
+    }
+
+    /// Returns the policy used for handling duplicate insertions.
+    pub fn policy(&self) -> DuplicatePolicy {
+        self.policy
+    }
+
+    /// Returns the number of bits used to store each value, which is 32 if
+    /// every value in `[0, n * 2^p)` fits in 32 bits and 64 otherwise.
+    pub fn bits_per_value(&self) -> u32 {
+        self.values.bits()
+    }
+
+    /// Returns how the values are searched when querying the set.
+    pub fn lookup(&self) -> Lookup {
+        self.lookup
+    }
+
+    /// Sets how the values are searched when querying the set, which is
+    /// [`Lookup::Binary`] by default.
+    pub fn set_lookup(&mut self, lookup: Lookup) {
+        self.lookup = lookup;
+    }
+
     /// Copies data from the reader and inserts into into the set.
     ///
     /// # Errors
     /// * If there is an error reading data from `reader`.
     /// * If more than `n` items have been inserted.
-    pub fn insert_from_reader<R: Read>(&mut self, mut reader: R) -> Result<(), Error> {
+    pub fn insert_from_reader<R: Read>(&mut self, mut reader: R) -> Result<bool, Error> {
         let mut vec = Vec::new();
         reader.read_exact(&mut vec)?;
         self.insert(&vec)
@@ -125,25 +310,145 @@ impl<D: Digest> UnpackedGcs<D> {
 
     /// Adds an entry to the set, and returns an error if more than N items are added.
     ///
+    /// Returns whether the value was not already present in the set. Under
+    /// [`DuplicatePolicy::Set`] a value which is already present is not
+    /// inserted again.
+    ///
     /// # Errors
     /// * If more than `n` items have been inserted.
-    pub fn insert<A: AsRef<[u8]>>(&mut self, input: A) -> Result<(), Error> {
+    pub fn insert<A: AsRef<[u8]>>(&mut self, input: A) -> Result<bool, Error> {
+        self.insert_reduced(digest_value::<D>(self.n as u64, self.p, input.as_ref()))
+    }
+
+    /// Adds a pre-computed 64 bit hash to the set, reducing it into the range
+    /// `[0, n * 2^p)` like the hash of an input passed to
+    /// [`insert`](UnpackedGcs::insert).
+    ///
+    /// The hash of an input is the first 8 bytes of its digest read as a big
+    /// endian integer, or the whole digest if it is shorter.
+    ///
+    /// # Errors
+    /// * If more than `n` items have been inserted.
+    pub fn insert_hash(&mut self, hash: u64) -> Result<bool, Error> {
+        self.insert_reduced(reduce(self.n as u64, self.p, hash))
+    }
+
+    /// Adds a value in the range `[0, n * 2^p)` to the set, without hashing or
+    /// reducing it.
+    ///
+    /// # Errors
+    /// * If more than `n` items have been inserted.
+    /// * If the value is not less than `n * 2^p`.
+    pub fn insert_reduced(&mut self, value: u64) -> Result<bool, Error> {
+        if value >= range(self.n as u64, self.p) {
+            return Err(Error::OutOfRange);
+        }
+
+        let (index, new) = match self.values.binary_search(value) {
+            Ok(_) if self.policy == DuplicatePolicy::Set => return Ok(false),
+            Ok(index) => (index, false),
+            Err(index) => (index, true),
+        };
+
         if self.values.len() < self.n {
-            self.values
-                .push(digest_value::<D>(self.n as u64, self.p, input.as_ref()));
-            self.values.sort();
-            Ok(())
+            self.values.insert(index, value);
+            Ok(new)
         } else {
             Err(Error::LimitReached)
         }
     }
 
+    /// Hashes items from an iterator and inserts them into the set.
+    ///
+    /// This is faster than calling [`insert`](UnpackedGcs::insert) for each
+    /// item when inserting many items at once. If inserting the items would
+    /// exceed the limit of `n` items, none of them are inserted.
+    ///
+    /// # Errors
+    /// * If more than `n` items would have been inserted.
+    pub fn extend<I, A>(&mut self, inputs: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = A>,
+        A: AsRef<[u8]>,
+    {
+        let mut values = inputs
+            .into_iter()
+            .map(|input| digest_value::<D>(self.n as u64, self.p, input.as_ref()))
+            .collect::<Vec<_>>();
+        values.sort_unstable();
+
+        self.merge_sorted(values)
+    }
+
+    /// Merges sorted values into the set, leaving it unchanged if that would
+    /// exceed the limit of `n` values.
+    ///
+    /// # Errors
+    /// * If more than `n` items would have been inserted.
+    fn merge_sorted(&mut self, values: Vec<u64>) -> Result<(), Error> {
+        let mut merged = Values::with_capacity(self.n, self.p, self.values.len() + values.len());
+        let mut existing = self.values.iter().peekable();
+        let mut values = values.into_iter().peekable();
+
+        loop {
+            let value = match (existing.peek(), values.peek()) {
+                (Some(a), Some(b)) if a <= b => existing.next(),
+                (Some(_), Some(_)) | (None, _) => values.next(),
+                (Some(_), None) => existing.next(),
+            };
+            let value = match value {
+                Some(value) => value,
+                None => break,
+            };
+
+            if self.policy == DuplicatePolicy::Set && merged.last() == Some(value) {
+                continue;
+            }
+            merged.push(value);
+        }
+
+        if merged.len() > self.n {
+            return Err(Error::LimitReached);
+        }
+
+        self.values = merged;
+        Ok(())
+    }
+
     /// Returns whether or not an input is contained in the set. If false the
     /// input is definitely not present, if true the input is probably present.
     pub fn contains<A: AsRef<[u8]>>(&self, input: A) -> bool {
+        self.contains_reduced(digest_value::<D>(self.n as u64, self.p, input.as_ref()))
+    }
+
+    /// Returns whether or not a pre-computed 64 bit hash is contained in the
+    /// set, see [`insert_hash`](UnpackedGcs::insert_hash).
+    pub fn contains_hash(&self, hash: u64) -> bool {
+        self.contains_reduced(reduce(self.n as u64, self.p, hash))
+    }
+
+    /// Returns whether or not a value in the range `[0, n * 2^p)` is contained
+    /// in the set, see [`insert_reduced`](UnpackedGcs::insert_reduced).
+    pub fn contains_reduced(&self, value: u64) -> bool {
+        match self.lookup {
+            Lookup::Binary => self.values.binary_search(value).is_ok(),
+            Lookup::Interpolation => self.values.interpolation_search(value),
+        }
+    }
+
+    /// Returns the number of times an input has been inserted into the set.
+    ///
+    /// The result may be an overestimate due to false positives, and is at
+    /// most 1 under [`DuplicatePolicy::Set`].
+    pub fn count<A: AsRef<[u8]>>(&self, input: A) -> usize {
+        let value = digest_value::<D>(self.n as u64, self.p, input.as_ref());
+        let start = self.values.partition_point(value);
+
         self.values
-            .binary_search(&digest_value::<D>(self.n as u64, self.p, input.as_ref()))
-            .is_ok()
+            .iter()
+            .skip(start)
+            .take_while(|&v| v == value)
+            .count()
     }
 
     /// Packs an `UnpackedGcs` into a `Gcs`.
@@ -151,23 +456,12 @@ impl<D: Digest> UnpackedGcs<D> {
     /// This will will reduce the memory footprint, but also reduce query
     /// performance.
     pub fn pack(&self) -> Gcs<D> {
-        let mut values = self.values.clone();
-
-        // Sort then calculate differences
-        values.sort();
-        for i in (1..values.len()).rev() {
-            values[i] -= values[i - 1];
-        }
-
-        // Apply golomb encoding
-        let mut data = BitVec::<BigEndian, u8>::new();
-        for val in values {
-            data.append(&mut golomb_encode(val, self.p))
-        }
+        let (_, data) = encode(self.values.iter(), self.p, 0);
 
         Gcs {
             n: self.n,
             p: self.p,
+            policy: self.policy,
             data,
             digest: self.digest,
         }
@@ -175,107 +469,450 @@ impl<D: Digest> UnpackedGcs<D> {
 }
 
 /// A packed Golomb-coded Set.
-#[derive(Clone, Debug, PartialEq)]
+///
+/// Sets are equal if they have the same `n`, `p` and values. The duplicate
+/// policy is not compared, as no encoding stores it: a set which is read
+/// uses [`DuplicatePolicy::Multiset`] if it contains duplicate values, and
+/// [`DuplicatePolicy::Set`] otherwise.
+#[derive(Clone, Debug)]
 pub struct Gcs<D: Digest> {
     n: usize,
     p: u8,
+    policy: DuplicatePolicy,
     data: BitVec,
     digest: PhantomData<D>,
 }
 
+impl<D: Digest> PartialEq for Gcs<D> {
+    fn eq(&self, other: &Self) -> bool {
+        self.n == other.n && self.p == other.p && self.data == other.data
+    }
+}
+
 impl<D: Digest> Gcs<D> {
-    /// Read a packed `Gcs` from any Reader.
+    /// Returns the maximum number of items `n` which the set was created for.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Returns `p`, where `1/2^p` is the probability of a false positive when
+    /// the set is full.
+    pub fn p(&self) -> u8 {
+        self.p
+    }
+
+    /// Read a packed `Gcs` from any Reader, as written by
+    /// [`write`](Gcs::write).
+    ///
+    /// Any duplicate values in the data are preserved, in which case the
+    /// returned set uses [`DuplicatePolicy::Multiset`]. The format has no
+    /// header, so fewer than 8 trailing zero bits are treated as
+    /// padding rather than as an encoded value.
     ///
     /// # Errors
     /// * If there is an error reading data from `reader`.
+    /// * If `n` or `p` are invalid, see [`Error::Parameters`].
+    /// * If the data is not a valid Golomb-Rice encoding.
+    /// * If a value is not less than `n * 2^p`, see [`Error::OutOfRange`].
     pub fn from_reader<R: Read>(reader: &mut R, n: usize, p: u8) -> Result<Self, Error> {
+        check_parameters(n as u64, p)?;
+
         let mut buf = Vec::new();
         reader.read_to_end(&mut buf)?;
-        let data = BitVec::<BigEndian, u8>::from_vec(buf);
+        let mut data = bits_from_vec(buf);
 
-        let mut iter = data.iter().peekable();
-        while iter.peek().is_some() {
-            golomb_decode(&mut iter, p)?;
-        }
+        let (_, len) = validate(&data, p, range(n as u64, p))?;
+        data.truncate(len);
 
-        Ok(Self {
+        let mut gcs = Self {
             n,
             p,
+            policy: DuplicatePolicy::default(),
             data,
             digest: PhantomData,
-        })
+        };
+        gcs.policy = infer_policy(gcs.iter());
+        Ok(gcs)
     }
 
     /// Writes a packed `Gcs` to a Writer.
     ///
+    /// The Golomb-Rice encoding of the values is written padded with zero bits
+    /// to a whole number of bytes, without a header, as in rasky's Python
+    /// implementation.
+    ///
     /// # Errors
     /// * If there is an error writing data to `writer`.
+    /// * If the set ends with values whose encoding would be read back as
+    ///   padding by [`from_reader`](Gcs::from_reader), which is only
+    ///   possible if `p < 7` and the last value is 0 or a duplicate.
     pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        let buf = self.data.clone().into_vec();
+        let limit = range(self.n as u64, self.p);
+        if validate(&bits_from_vec(buf.clone()), self.p, limit)?.0 != self.iter().count() {
+            return Err(Error::Padding);
+        }
+
+        writer.write_all(&buf)?;
+        Ok(())
+    }
+
+    /// Read a packed `Gcs` from any Reader, as written by
+    /// [`write_counted`](Gcs::write_counted).
+    ///
+    /// Any duplicate values in the data are preserved, in which case the
+    /// returned set uses [`DuplicatePolicy::Multiset`].
+    ///
+    /// # Errors
+    /// * If there is an error reading data from `reader`.
+    /// * If `n` or `p` are invalid, see [`Error::Parameters`].
+    /// * If the header is missing.
+    /// * If the data does not contain the number of values given in the header.
+    /// * If a value is not less than `n * 2^p`, see [`Error::OutOfRange`].
+    pub fn from_counted_reader<R: Read>(reader: &mut R, n: usize, p: u8) -> Result<Self, Error> {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+
+        Self::from_vec(buf, n, p)
+    }
+
+    /// Creates a `Gcs` from the number of encoded values followed by the
+    /// values, as written by [`write_counted`](Gcs::write_counted).
+    ///
+    /// # Errors
+    /// * If `n` or `p` are invalid, see [`Error::Parameters`].
+    /// * If `buf` is shorter than the header.
+    /// * If `buf` does not contain the number of values given in the header.
+    fn from_vec(mut buf: Vec<u8>, n: usize, p: u8) -> Result<Self, Error> {
+        check_parameters(n as u64, p)?;
+        if buf.len() < 8 {
+            return Err(Error::Header);
+        }
+
+        let len = byteorder::BigEndian::read_u64(&buf[..8]);
+        buf.drain(..8);
+        Self::from_parts(n, p, len, buf)
+    }
+
+    /// Writes a packed `Gcs` to a Writer, preceded by the number of values.
+    ///
+    /// The number of encoded values is written as an 8 byte big endian integer,
+    /// followed by the same data as [`write`](Gcs::write). Unlike `write`, any
+    /// set can be read back exactly, as trailing zero bits are never mistaken
+    /// for a value. Unlike [`write_canonical`](Gcs::write_canonical),
+    /// duplicates are preserved and `n` and `p` are not included.
+    ///
+    /// # Errors
+    /// * If there is an error writing data to `writer`.
+    pub fn write_counted<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        writer.write_u64::<byteorder::BigEndian>(self.iter().count() as u64)?;
         writer.write_all(&self.data.clone().into_vec())?;
         Ok(())
     }
 
-    /// Returns whether or not an input is contained in the set. If false the
-    /// input is definitely not present, if true the input is probably present.
+    /// Read a `Gcs` in its canonical encoding from any Reader.
+    ///
+    /// See [`write_canonical`](Gcs::write_canonical) for a description of the
+    /// encoding.
     ///
     /// # Errors
-    /// * If the inner data is not a valid Golomb-Rice encoding.
-    pub fn contains<A: AsRef<[u8]>>(&self, input: A) -> bool {
-        let input = digest_value::<D>(self.n as u64, self.p, input.as_ref());
+    /// * If there is an error reading data from `reader`.
+    /// * If the header is malformed.
+    /// * If `n` or `p` are invalid, see [`Error::Parameters`].
+    /// * If the data does not contain the number of values given in the header.
+    /// * If a value is not less than `n * 2^p`, see [`Error::OutOfRange`].
+    pub fn from_canonical_reader<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != CANONICAL_MAGIC {
+            return Err(Error::Header);
+        }
 
-        let mut iter = self.data.iter().peekable();
+        let p = reader.read_u8()?;
+        let n = reader.read_u64::<byteorder::BigEndian>()?;
+        check_parameters(n, p)?;
+        let len = reader.read_u64::<byteorder::BigEndian>()?;
 
-        let mut last = 0;
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
 
-        while iter.peek().is_some() {
-            // This should never happen because data is checked on creation
-            let decoded = golomb_decode(&mut iter, self.p).expect("Golomb decoding failed");
+        Self::from_parts(n as usize, p, len, buf)
+    }
 
-            if input == (decoded + last) {
-                return true;
-            } else {
-                last += decoded;
-            }
+    /// Creates a `Gcs` from the Golomb-Rice encoding of exactly `len` values,
+    /// padded with zero bits to a whole number of bytes.
+    ///
+    /// # Errors
+    /// * If `buf` does not contain exactly `len` encoded values.
+    /// * If a value is not less than `n * 2^p`.
+    fn from_parts(n: usize, p: u8, len: u64, buf: Vec<u8>) -> Result<Self, Error> {
+        let mut data = bits_from_vec(buf);
+        let limit = range(n as u64, p);
+
+        let mut iter = data.iter();
+        let mut last = 0u64;
+        for _ in 0..len {
+            last = last
+                .checked_add(golomb_decode(&mut iter, p)?)
+                .filter(|&value| value < limit)
+                .ok_or(Error::OutOfRange)?;
         }
+        if !is_padding(&iter) {
+            return Err(Error::Decode);
+        }
+        let end = data.len() - iter.len();
+        data.truncate(end);
 
-        false
+        let mut gcs = Self {
+            n,
+            p,
+            policy: DuplicatePolicy::default(),
+            data,
+            digest: PhantomData,
+        };
+        gcs.policy = infer_policy(gcs.iter());
+        Ok(gcs)
     }
 
-    /// Unpacks a `Gcs` into an `UnpackedGcs`.
+    /// Writes the canonical encoding of a `Gcs` to a Writer.
     ///
-    /// This will will increase query performance, but also increase the memory
-    /// footprint.
+    /// Sets with the same `n`, `p` and members always have the same canonical
+    /// encoding, regardless of the order or number of times each member was
+    /// inserted, and of whether they were unpacked and packed again. It
+    /// consists of the following fields:
+    ///
+    /// | Length  | Content                                                   |
+    /// |---------|-----------------------------------------------------------|
+    /// | 4 bytes | The magic bytes `GCS1`                                    |
+    /// | 1 byte  | `p`                                                       |
+    /// | 8 bytes | `n`, as a big endian integer                              |
+    /// | 8 bytes | The number of distinct values `m`, as a big endian integer |
+    /// | rest    | The Golomb-Rice encoding of the `m` distinct values in ascending order, padded with zero bits to a whole number of bytes |
+    ///
+    /// # Errors
+    /// * If there is an error writing data to `writer`.
+    pub fn write_canonical<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        let mut previous = None;
+        let (len, data) = encode(
+            self.iter()
+                .filter(|&value| previous.replace(value) != Some(value)),
+            self.p,
+            0,
+        );
+
+        writer.write_all(&CANONICAL_MAGIC)?;
+        writer.write_u8(self.p)?;
+        writer.write_u64::<byteorder::BigEndian>(self.n as u64)?;
+        writer.write_u64::<byteorder::BigEndian>(len as u64)?;
+        writer.write_all(&data.into_vec())?;
+        Ok(())
+    }
+
+    /// Returns the hash of the canonical encoding of a `Gcs`, computed with
+    /// the digest `D2`.
+    ///
+    /// Sets with the same `n`, `p` and members always have the same content
+    /// hash, see [`write_canonical`](Gcs::write_canonical).
+    pub fn content_hash<D2: Digest>(&self) -> GenericArray<u8, D2::OutputSize> {
+        let mut buf = Vec::new();
+        self.write_canonical(&mut buf)
+            .expect("Writing to a Vec failed");
+        D2::digest(&buf)
+    }
+
+    /// Returns whether or not an input is contained in the set. If false the
+    /// input is definitely not present, if true the input is probably present.
     ///
     /// # Errors
     /// * If the inner data is not a valid Golomb-Rice encoding.
-    pub fn unpack(&self) -> UnpackedGcs<D> {
-        let mut values = {
-            let mut iter = self.data.iter().peekable();
-            let mut values = Vec::new();
+    pub fn contains<A: AsRef<[u8]>>(&self, input: A) -> bool {
+        self.contains_reduced(digest_value::<D>(self.n as u64, self.p, input.as_ref()))
+    }
 
-            while iter.peek().is_some() {
-                // This should never happen because data is checked on creation
-                values.push(golomb_decode(&mut iter, self.p).expect("Golomb decoding failed"));
-            }
+    /// Returns whether or not a pre-computed 64 bit hash is contained in the
+    /// set, see [`UnpackedGcs::insert_hash`].
+    pub fn contains_hash(&self, hash: u64) -> bool {
+        self.contains_reduced(reduce(self.n as u64, self.p, hash))
+    }
 
-            values
-        };
+    /// Returns the number of times an input has been inserted into the set.
+    ///
+    /// The result may be an overestimate due to false positives, and is at
+    /// most 1 under [`DuplicatePolicy::Set`].
+    pub fn count<A: AsRef<[u8]>>(&self, input: A) -> usize {
+        self.count_value(digest_value::<D>(self.n as u64, self.p, input.as_ref()))
+    }
 
-        for i in 1..values.len() {
-            values[i] += values[i - 1];
-        }
+    /// Returns whether or not a value in the range `[0, n * 2^p)` is contained
+    /// in the set, see [`UnpackedGcs::insert_reduced`].
+    pub fn contains_reduced(&self, input: u64) -> bool {
+        self.iter()
+            .take_while(|&value| value <= input)
+            .any(|value| value == input)
+    }
 
-        values.sort();
+    /// Returns the number of times a hashed value is contained in the set.
+    fn count_value(&self, input: u64) -> usize {
+        self.iter()
+            .skip_while(|&value| value < input)
+            .take_while(|&value| value == input)
+            .count()
+    }
 
+    /// Returns an iterator over the hashed values stored in the set, in
+    /// ascending order.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            bits: self.data.iter().peekable(),
+            p: self.p,
+            last: 0,
+        }
+    }
+
+    /// Unpacks a `Gcs` into an `UnpackedGcs`.
+    ///
+    /// This will will increase query performance, but also increase the memory
+    /// footprint.
+    pub fn unpack(&self) -> UnpackedGcs<D> {
         UnpackedGcs {
             n: self.n,
             p: self.p,
-            values,
+            policy: self.policy,
+            lookup: Lookup::default(),
+            values: Values::from_vec(self.n, self.p, self.iter().collect()),
             digest: self.digest,
         }
     }
 }
 
+/// An iterator over the hashed values of a packed `Gcs`.
+///
+/// This `struct` is created by the [`iter`](Gcs::iter) method on [`Gcs`].
+#[derive(Clone, Debug)]
+pub struct Iter<'a> {
+    bits: Peekable<bitvec::slice::Iter<'a, BigEndian, u8>>,
+    p: u8,
+    last: u64,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        self.bits.peek()?;
+
+        // This should never happen because data is checked on creation
+        let decoded = golomb_decode(&mut self.bits, self.p).expect("Golomb decoding failed");
+        self.last += decoded;
+
+        Some(self.last)
+    }
+}
+
+/// Magic bytes at the start of the canonical encoding of a `Gcs`.
+const CANONICAL_MAGIC: [u8; 4] = *b"GCS1";
+
+/// Reads bytes prefixed by their length as an 8 byte big endian integer, as
+/// written by `write_bytes`.
+///
+/// # Errors
+/// * If there is an error reading data from `reader`.
+/// * If `reader` ends before the given length.
+fn read_bytes<R: Read>(reader: &mut R) -> Result<Vec<u8>, Error> {
+    let len = reader.read_u64::<byteorder::BigEndian>()?;
+
+    let mut buf = Vec::new();
+    reader.take(len).read_to_end(&mut buf)?;
+    if buf.len() as u64 != len {
+        return Err(Error::Header);
+    }
+    Ok(buf)
+}
+
+/// Writes bytes prefixed by their length as an 8 byte big endian integer.
+///
+/// # Errors
+/// * If there is an error writing data to `writer`.
+fn write_bytes<W: Write>(writer: &mut W, bytes: &[u8]) -> Result<(), Error> {
+    writer.write_u64::<byteorder::BigEndian>(bytes.len() as u64)?;
+    writer.write_all(bytes)?;
+    Ok(())
+}
+
+/// Converts bytes into bits, most significant bit first.
+fn bits_from_vec(buf: Vec<u8>) -> BitVec {
+    // bitvec frees the allocation of an empty but allocated vector incorrectly
+    if buf.is_empty() {
+        BitVec::new()
+    } else {
+        BitVec::from_vec(buf)
+    }
+}
+
+/// Perform Golomb-Rice encoding of the differences between sorted values, the
+/// first of which is encoded as its difference from `last`. Returns the number
+/// of values encoded along with the encoding.
+fn encode<I: IntoIterator<Item = u64>>(values: I, p: u8, mut last: u64) -> (usize, BitVec) {
+    let mut data = BitVec::new();
+    let mut len = 0;
+
+    for value in values {
+        data.append(&mut golomb_encode(value - last, p));
+        len += 1;
+        last = value;
+    }
+
+    (len, data)
+}
+
+/// Checks that `data` is a valid Golomb-Rice encoding, returning the number of
+/// encoded values and the number of bits they occupy.
+///
+/// Fewer than 8 trailing zero bits are treated as padding rather than as an
+/// encoded value.
+///
+/// # Errors
+/// * If `data` is not a valid Golomb-Rice encoding
+/// * If a decoded value is not less than `limit`.
+fn validate(data: &BitVec, p: u8, limit: u64) -> Result<(usize, usize), Error> {
+    let mut iter = data.iter();
+    let mut len = 0;
+    let mut end = 0;
+    let mut last = 0u64;
+
+    while !is_padding(&iter) {
+        last = last
+            .checked_add(golomb_decode(&mut iter, p)?)
+            .filter(|&value| value < limit)
+            .ok_or(Error::OutOfRange)?;
+        len += 1;
+        end = data.len() - iter.len();
+    }
+
+    Ok((len, end))
+}
+
+/// Returns the policy of a set read from an encoding with the given values in
+/// ascending order, as only sets using [`DuplicatePolicy::Multiset`] can
+/// contain duplicates.
+fn infer_policy<I: IntoIterator<Item = u64>>(values: I) -> DuplicatePolicy {
+    let mut last = None;
+    for value in values {
+        if last == Some(value) {
+            return DuplicatePolicy::Multiset;
+        }
+        last = Some(value);
+    }
+
+    DuplicatePolicy::Set
+}
+
+/// Returns whether the remaining bits are the zero padding which completes
+/// the final byte of an encoding.
+fn is_padding(iter: &bitvec::slice::Iter<BigEndian, u8>) -> bool {
+    iter.len() < 8 && !iter.clone().any(|bit| bit)
+}
+
 /// Perform Golomb-Rice encoding of n, with modulus 2^p.
 ///
 /// # Panics
@@ -307,6 +944,7 @@ fn golomb_encode(n: u64, p: u8) -> BitVec {
 ///
 /// # Errors
 /// * If `iter` is not a valid Golomb-Rice encoding
+/// * If the decoded value does not fit in 64 bits
 fn golomb_decode<I>(iter: &mut I, p: u8) -> Result<u64, Error>
 where
     I: Iterator<Item = bool>,
@@ -333,24 +971,66 @@ where
     }
     rem >>= 1;
 
-    // push quo * p + rem
-    Ok(quo * 2u64.pow(u32::from(p)) + rem)
+    // push quo * p + rem, which crafted data can make overflow
+    2u64.checked_pow(u32::from(p))
+        .and_then(|m| quo.checked_mul(m))
+        .and_then(|value| value.checked_add(rem))
+        .ok_or(Error::Decode)
 }
 
 fn digest_value<D: Digest>(n: u64, p: u8, input: &[u8]) -> u64 {
-    let val = if D::output_size() < 8 {
+    reduce(n, p, digest_hash::<D>(input))
+}
+
+/// Returns the first 8 bytes of the digest of an input as an integer, or the
+/// whole digest if it is shorter.
+fn digest_hash<D: Digest>(input: &[u8]) -> u64 {
+    hash_from_digest(&D::digest(input))
+}
+
+/// Returns the first 8 bytes of a digest as an integer, or the whole digest if
+/// it is shorter.
+fn hash_from_digest(digest: &[u8]) -> u64 {
+    if digest.len() < 8 {
         let mut buf = [0u8; 8];
-        let digest = D::digest(input);
-        for i in 0..D::output_size() {
-            buf[i + D::output_size()] = digest[i];
-        }
+        buf[8 - digest.len()..].copy_from_slice(digest);
 
         byteorder::BigEndian::read_u64(&buf)
     } else {
-        byteorder::BigEndian::read_u64(&D::digest(input)[..8])
-    };
+        byteorder::BigEndian::read_u64(&digest[..8])
+    }
+}
+
+/// Reduces a hash into the range `[0, n * 2^p)`.
+fn reduce(n: u64, p: u8, hash: u64) -> u64 {
+    hash % range(n, p)
+}
+
+/// Returns the number of possible values of a set, `n * 2^p`.
+///
+/// # Panics
+/// * Panics if `n * 2^p` does not fit in 64 bits, which sets read from an
+///   encoding are checked for by [`check_parameters`].
+fn range(n: u64, p: u8) -> u64 {
+    checked_range(n, p).expect("n * 2^p does not fit in 64 bits")
+}
+
+/// Returns the number of possible values of a set, `n * 2^p`, or `None` if it
+/// does not fit in 64 bits.
+fn checked_range(n: u64, p: u8) -> Option<u64> {
+    2u64.checked_pow(u32::from(p))?.checked_mul(n)
+}
+
+/// Checks the parameters of a set read from an encoding.
+///
+/// # Errors
+/// * If `n == 0`, `p == 0` or `n * 2^p` does not fit in 64 bits.
+fn check_parameters(n: u64, p: u8) -> Result<(), Error> {
+    if n == 0 || p == 0 || checked_range(n, p).is_none() {
+        return Err(Error::Parameters);
+    }
 
-    val % (n * 2u64.pow(u32::from(p)))
+    Ok(())
 }
 
 #[cfg(test)]
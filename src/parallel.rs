@@ -0,0 +1,80 @@
+//! Parallel construction of sets, enabled by the `rayon` feature.
+
+use {
+    crate::{digest_value, encode, values::Values, Error, Gcs, UnpackedGcs},
+    bitvec::prelude::BitVec,
+    digest::Digest,
+    rayon::prelude::*,
+};
+
+/// Number of values encoded by each task when packing in parallel.
+const CHUNK_LEN: usize = 1 << 14;
+
+impl<D: Digest> UnpackedGcs<D> {
+    /// Hashes items from a parallel iterator and inserts them into the set.
+    ///
+    /// If inserting the items would exceed the limit of `n` items, none of them
+    /// are inserted.
+    ///
+    /// # Errors
+    /// * If more than `n` items would have been inserted.
+    pub fn par_extend<I, A>(&mut self, inputs: I) -> Result<(), Error>
+    where
+        I: IntoParallelIterator<Item = A>,
+        A: AsRef<[u8]>,
+    {
+        let (n, p) = (self.n as u64, self.p);
+
+        let mut values = inputs
+            .into_par_iter()
+            .map(|input| digest_value::<D>(n, p, input.as_ref()))
+            .collect::<Vec<_>>();
+        values.par_sort_unstable();
+
+        self.merge_sorted(values)
+    }
+
+    /// Packs an `UnpackedGcs` into a `Gcs`, encoding chunks of the values in
+    /// parallel.
+    ///
+    /// The result is identical to that of [`pack`](UnpackedGcs::pack).
+    pub fn par_pack(&self) -> Gcs<D> {
+        let chunks = match &self.values {
+            Values::Narrow(values) => encode_chunks(values, self.p),
+            Values::Wide(values) => encode_chunks(values, self.p),
+        };
+
+        let mut data = BitVec::new();
+        for mut chunk in chunks {
+            data.append(&mut chunk);
+        }
+
+        Gcs {
+            n: self.n,
+            p: self.p,
+            policy: self.policy,
+            data,
+            digest: self.digest,
+        }
+    }
+}
+
+/// Encodes chunks of the values in parallel.
+fn encode_chunks<T>(values: &[T], p: u8) -> Vec<BitVec>
+where
+    T: Copy + Into<u64> + Sync,
+{
+    values
+        .par_chunks(CHUNK_LEN)
+        .enumerate()
+        .map(|(i, chunk)| {
+            // Each chunk starts with the difference from the end of the last
+            let last = if i == 0 {
+                0
+            } else {
+                values[i * CHUNK_LEN - 1].into()
+            };
+            encode(chunk.iter().map(|&value| value.into()), p, last).1
+        })
+        .collect()
+}
@@ -0,0 +1,247 @@
+//! Patches between two versions of a packed set.
+
+use {
+    crate::{
+        bits_from_vec, check_parameters, encode, golomb_decode, is_padding, range, read_bytes,
+        write_bytes, Error, Gcs,
+    },
+    byteorder::{BigEndian, ReadBytesExt, WriteBytesExt},
+    digest::Digest,
+    std::{
+        cmp::Ordering,
+        io::{Read, Write},
+        marker::PhantomData,
+    },
+};
+
+/// Magic bytes at the start of an encoded [`GcsPatch`].
+const PATCH_MAGIC: [u8; 4] = *b"GCSP";
+
+/// The values removed from and added to a packed set, created by
+/// [`Gcs::diff`] and applied with [`Gcs::apply_patch`].
+///
+/// Values are counted as a multiset, so a patch also records changes to the
+/// number of times a value is present.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GcsPatch {
+    n: usize,
+    p: u8,
+    removed: Vec<u64>,
+    added: Vec<u64>,
+}
+
+impl GcsPatch {
+    /// Returns `n` of the sets the patch applies to.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Returns `p` of the sets the patch applies to.
+    pub fn p(&self) -> u8 {
+        self.p
+    }
+
+    /// Returns the values removed from the old set, in ascending order.
+    pub fn removed(&self) -> &[u64] {
+        &self.removed
+    }
+
+    /// Returns the values added to the old set, in ascending order.
+    pub fn added(&self) -> &[u64] {
+        &self.added
+    }
+
+    /// Returns whether or not the patch leaves a set unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty()
+    }
+
+    /// Read a `GcsPatch` from any Reader, as written by
+    /// [`write`](GcsPatch::write).
+    ///
+    /// # Errors
+    /// * If there is an error reading data from `reader`.
+    /// * If the header is malformed.
+    /// * If `n` or `p` are invalid, see [`Error::Parameters`].
+    /// * If the removed or added values are not a valid Golomb-Rice encoding,
+    ///   or are out of range for `n` and `p`.
+    pub fn from_reader<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != PATCH_MAGIC {
+            return Err(Error::Header);
+        }
+
+        let p = reader.read_u8()?;
+        let n = reader.read_u64::<BigEndian>()?;
+        check_parameters(n, p)?;
+        let range = range(n, p);
+        let removed = read_values(reader, range)?;
+        let added = read_values(reader, range)?;
+
+        Ok(Self {
+            n: n as usize,
+            p,
+            removed,
+            added,
+        })
+    }
+
+    /// Writes a `GcsPatch` to a Writer.
+    ///
+    /// The removed and added values are each Golomb-Rice encoded with a
+    /// parameter chosen from their number, so a patch changing few values is
+    /// much smaller than the set it applies to. It consists of the following
+    /// fields, with the last four repeated for the removed and then the added
+    /// values:
+    ///
+    /// | Length  | Content                                                   |
+    /// |---------|-----------------------------------------------------------|
+    /// | 4 bytes | The magic bytes `GCSP`                                    |
+    /// | 1 byte  | `p`                                                       |
+    /// | 8 bytes | `n`, as a big endian integer                              |
+    /// | 8 bytes | The number of values `m`, as a big endian integer         |
+    /// | 1 byte  | The Golomb-Rice parameter `k`                             |
+    /// | 8 bytes | The length of the encoding in bytes, as a big endian integer |
+    /// | varies  | The Golomb-Rice encoding with parameter `k` of the `m` values in ascending order, padded with zero bits to a whole number of bytes |
+    ///
+    /// # Errors
+    /// * If there is an error writing data to `writer`.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        writer.write_all(&PATCH_MAGIC)?;
+        writer.write_u8(self.p)?;
+        writer.write_u64::<BigEndian>(self.n as u64)?;
+
+        let range = range(self.n as u64, self.p);
+        write_values(writer, &self.removed, range)?;
+        write_values(writer, &self.added, range)
+    }
+}
+
+impl<D: Digest> Gcs<D> {
+    /// Returns the patch which turns the set into `new`, containing the values
+    /// only in one of them.
+    ///
+    /// # Errors
+    /// * If the sets have a different `n` or `p`.
+    pub fn diff(&self, new: &Self) -> Result<GcsPatch, Error> {
+        if self.n != new.n || self.p != new.p {
+            return Err(Error::Parameters);
+        }
+
+        let (mut old, mut new) = (self.iter().peekable(), new.iter().peekable());
+        let (mut removed, mut added) = (Vec::new(), Vec::new());
+        loop {
+            let ordering = match (old.peek(), new.peek()) {
+                (Some(o), Some(n)) => o.cmp(n),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => break,
+            };
+
+            match ordering {
+                Ordering::Less => removed.extend(old.next()),
+                Ordering::Greater => added.extend(new.next()),
+                Ordering::Equal => {
+                    old.next();
+                    new.next();
+                }
+            }
+        }
+
+        Ok(GcsPatch {
+            n: self.n,
+            p: self.p,
+            removed,
+            added,
+        })
+    }
+
+    /// Applies a patch created by [`diff`](Gcs::diff) to the set, returning
+    /// the new set. The result is encoded identically to the set the patch was
+    /// created from, and keeps the duplicate policy of this set.
+    ///
+    /// # Errors
+    /// * If the patch has a different `n` or `p`.
+    /// * If a removed value is not in the set.
+    pub fn apply_patch(&self, patch: &GcsPatch) -> Result<Self, Error> {
+        if self.n != patch.n || self.p != patch.p {
+            return Err(Error::Parameters);
+        }
+
+        let mut removed = patch.removed.iter().peekable();
+        let mut kept = Vec::new();
+        for value in self.iter() {
+            match removed.peek().map(|&&other| other.cmp(&value)) {
+                Some(Ordering::Less) => return Err(Error::Patch),
+                Some(Ordering::Equal) => {
+                    removed.next();
+                }
+                _ => kept.push(value),
+            }
+        }
+        if removed.next().is_some() {
+            return Err(Error::Patch);
+        }
+
+        let mut added = patch.added.iter().cloned().peekable();
+        let mut values = Vec::with_capacity(kept.len() + patch.added.len());
+        for value in kept {
+            while let Some(other) = added.next_if(|&other| other < value) {
+                values.push(other);
+            }
+            values.push(value);
+        }
+        values.extend(added);
+
+        let (_, data) = encode(values, self.p, 0);
+        Ok(Self {
+            n: self.n,
+            p: self.p,
+            policy: self.policy,
+            data,
+            digest: PhantomData,
+        })
+    }
+}
+
+/// Returns the Golomb-Rice parameter for `len` ascending values in
+/// `[0, range)`, the logarithm of their mean difference.
+fn parameter(len: usize, range: u64) -> u8 {
+    let mean = range / (len as u64 + 1);
+    (63 - mean.max(1).leading_zeros() as u8).max(1)
+}
+
+fn read_values<R: Read>(reader: &mut R, range: u64) -> Result<Vec<u64>, Error> {
+    let len = reader.read_u64::<BigEndian>()?;
+    // No more than the parameter `write_values` chooses for an empty list
+    let k = reader.read_u8()?;
+    if k == 0 || k > parameter(0, range) {
+        return Err(Error::Header);
+    }
+
+    let data = bits_from_vec(read_bytes(reader)?);
+    let mut iter = data.iter();
+    let (mut values, mut last) = (Vec::new(), 0u64);
+    for _ in 0..len {
+        last = last
+            .checked_add(golomb_decode(&mut iter, k)?)
+            .filter(|&value| value < range)
+            .ok_or(Error::Decode)?;
+        values.push(last);
+    }
+    if !is_padding(&iter) {
+        return Err(Error::Decode);
+    }
+
+    Ok(values)
+}
+
+fn write_values<W: Write>(writer: &mut W, values: &[u64], range: u64) -> Result<(), Error> {
+    let k = parameter(values.len(), range);
+    let (_, data) = encode(values.iter().cloned(), k, 0);
+
+    writer.write_u64::<BigEndian>(values.len() as u64)?;
+    writer.write_u8(k)?;
+    write_bytes(writer, &data.into_vec())
+}
@@ -0,0 +1,307 @@
+//! Reconciliation of item sets between two peers, using packed sets as
+//! sketches.
+//!
+//! The [`Initiator`] sends a sketch of its items, to which the [`Responder`]
+//! replies with its items which are definitely not in the sketch. Items which
+//! are in the sketch may be false positives, so the initiator then sends a
+//! sketch with a higher `p`, and the responder replies with those of the
+//! previously matching items which are not in it. Afterwards the initiator has
+//! all of the responder's items, unless one of them was a false positive in
+//! both sketches.
+//!
+//! Only the initiator learns of missing items, so to synchronize both peers
+//! run the protocol again with their roles swapped.
+//!
+//! ```rust
+//! use {
+//!     golomb_set::reconcile::{Initiator, Responder},
+//!     twox_hash::XxHash,
+//! };
+//!
+//! let mut initiator = Initiator::<XxHash>::new(vec!["alpha", "beta"], 8);
+//! let mut responder = Responder::<XxHash>::new(vec!["beta", "gamma"]);
+//!
+//! let mut message = initiator.start()?;
+//! loop {
+//!     let reply = responder.receive(message)?;
+//!     match initiator.receive(reply)? {
+//!         Some(next) => message = next,
+//!         None => break,
+//!     }
+//! }
+//!
+//! assert_eq!(initiator.received(), &[b"gamma".to_vec()]);
+//! # Ok::<(), golomb_set::Error>(())
+//! ```
+
+use {
+    crate::{check_parameters, digest_value, read_bytes, write_bytes, Error, Gcs, UnpackedGcs},
+    byteorder::{BigEndian, ReadBytesExt, WriteBytesExt},
+    digest::Digest,
+    std::{
+        cmp::Ordering,
+        io::{Read, Write},
+        marker::PhantomData,
+    },
+};
+
+/// The number of bits added to `p` for the second round.
+const SECOND_ROUND_BITS: u8 = 16;
+
+/// A message sent between the peers.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    /// A sketch of the initiator's items, in the canonical encoding of a packed
+    /// set, see [`Gcs::write_canonical`].
+    Sketch(Vec<u8>),
+    /// Items of the responder which are not in the last sketch.
+    Items {
+        /// The items which are not in the sketch.
+        items: Vec<Vec<u8>>,
+        /// Whether any items were in the sketch, in which case the initiator
+        /// should send a sketch with a higher `p` if it has not already.
+        more: bool,
+    },
+}
+
+impl Message {
+    /// Read a message from any Reader, as written by
+    /// [`write`](Message::write).
+    ///
+    /// # Errors
+    /// * If there is an error reading data from `reader`.
+    /// * If the message type is unknown.
+    pub fn from_reader<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        match reader.read_u8()? {
+            0 => Ok(Message::Sketch(read_bytes(reader)?)),
+            1 => {
+                let more = reader.read_u8()? != 0;
+                let len = reader.read_u64::<BigEndian>()?;
+                let items = (0..len)
+                    .map(|_| read_bytes(reader))
+                    .collect::<Result<_, _>>()?;
+
+                Ok(Message::Items { items, more })
+            }
+            _ => Err(Error::Header),
+        }
+    }
+
+    /// Writes a message to a Writer.
+    ///
+    /// A sketch is written as a 0 byte followed by the length of the encoded
+    /// set and the set. Items are written as a 1 byte, a byte which is 1 if
+    /// there may be more items, the number of items and then each item's
+    /// length followed by the item. Lengths are 8 byte big endian integers.
+    ///
+    /// # Errors
+    /// * If there is an error writing data to `writer`.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        match self {
+            Message::Sketch(sketch) => {
+                writer.write_u8(0)?;
+                write_bytes(writer, sketch)?;
+            }
+            Message::Items { items, more } => {
+                writer.write_u8(1)?;
+                writer.write_u8(*more as u8)?;
+                writer.write_u64::<BigEndian>(items.len() as u64)?;
+                for item in items {
+                    write_bytes(writer, item)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The peer which starts the protocol by sending a sketch of its items, and
+/// receives the items of the [`Responder`] which it lacks.
+#[derive(Debug)]
+pub struct Initiator<D: Digest> {
+    items: Vec<Vec<u8>>,
+    p: u8,
+    round: u8,
+    received: Vec<Vec<u8>>,
+    digest: PhantomData<D>,
+}
+
+impl<D: Digest> Initiator<D> {
+    /// Creates a new `Initiator` with the given items, whose first sketch will
+    /// have a false positive probability of `1 / 2^p`.
+    pub fn new<I, A>(items: I, p: u8) -> Self
+    where
+        I: IntoIterator<Item = A>,
+        A: AsRef<[u8]>,
+    {
+        Self {
+            items: items
+                .into_iter()
+                .map(|item| item.as_ref().to_vec())
+                .collect(),
+            p,
+            round: 0,
+            received: Vec::new(),
+            digest: PhantomData,
+        }
+    }
+
+    /// Returns the first message to send to the responder.
+    ///
+    /// # Errors
+    /// * If the protocol has already been started.
+    /// * If `p` is 0, or `n * 2^p` does not fit in 64 bits where `n` is the
+    ///   number of items, see [`Error::Parameters`].
+    pub fn start(&mut self) -> Result<Message, Error> {
+        if self.round != 0 {
+            return Err(Error::Protocol);
+        }
+        check_parameters(self.n() as u64, self.p)?;
+
+        let sketch = self.sketch(self.p)?;
+        self.round = 1;
+        Ok(sketch)
+    }
+
+    /// Handles a message from the responder, returning the next message to
+    /// send or `None` if the protocol is finished.
+    ///
+    /// # Errors
+    /// * If the message is not a reply to the last sketch.
+    pub fn receive(&mut self, message: Message) -> Result<Option<Message>, Error> {
+        let (items, more) = match message {
+            Message::Items { items, more } if self.round == 1 || self.round == 2 => (items, more),
+            _ => return Err(Error::Protocol),
+        };
+        self.received.extend(items);
+
+        if more && self.round == 1 {
+            self.round = 2;
+            Ok(Some(self.sketch(second_round_p(self.n(), self.p))?))
+        } else {
+            self.round = 3;
+            Ok(None)
+        }
+    }
+
+    /// Returns whether or not the protocol is finished.
+    pub fn is_finished(&self) -> bool {
+        self.round == 3
+    }
+
+    /// Returns the items received from the responder.
+    pub fn received(&self) -> &[Vec<u8>] {
+        &self.received
+    }
+
+    /// Returns the items received from the responder, consuming the
+    /// `Initiator`.
+    pub fn into_received(self) -> Vec<Vec<u8>> {
+        self.received
+    }
+
+    /// Returns `n` for the sketches, which must be at least 1.
+    fn n(&self) -> usize {
+        self.items.len().max(1)
+    }
+
+    /// Returns a sketch of the items with a false positive probability of
+    /// `1 / 2^p`.
+    ///
+    /// # Errors
+    /// * If the items do not fit in the sketch.
+    fn sketch(&self, p: u8) -> Result<Message, Error> {
+        let mut gcs = UnpackedGcs::<D>::new(self.n(), p);
+        gcs.extend(&self.items)?;
+
+        let mut buf = Vec::new();
+        gcs.pack().write_canonical(&mut buf)?;
+        Ok(Message::Sketch(buf))
+    }
+}
+
+/// The peer which replies to the sketches of the [`Initiator`] with the items
+/// it lacks.
+#[derive(Debug)]
+pub struct Responder<D: Digest> {
+    items: Vec<Vec<u8>>,
+    /// The indices of the items which were in the last sketch.
+    candidates: Vec<usize>,
+    round: u8,
+    digest: PhantomData<D>,
+}
+
+impl<D: Digest> Responder<D> {
+    /// Creates a new `Responder` with the given items.
+    pub fn new<I, A>(items: I) -> Self
+    where
+        I: IntoIterator<Item = A>,
+        A: AsRef<[u8]>,
+    {
+        let items = items
+            .into_iter()
+            .map(|item| item.as_ref().to_vec())
+            .collect::<Vec<_>>();
+
+        Self {
+            candidates: (0..items.len()).collect(),
+            items,
+            round: 0,
+            digest: PhantomData,
+        }
+    }
+
+    /// Handles a sketch from the initiator, returning the reply containing the
+    /// items which are not in it.
+    ///
+    /// # Errors
+    /// * If the message is not a sketch, or more than two sketches are
+    ///   received.
+    /// * If the sketch is not a valid canonical encoding.
+    pub fn receive(&mut self, message: Message) -> Result<Message, Error> {
+        let sketch = match message {
+            Message::Sketch(sketch) if self.round < 2 => sketch,
+            _ => return Err(Error::Protocol),
+        };
+        let gcs = Gcs::<D>::from_canonical_reader(&mut &sketch[..])?;
+        self.round += 1;
+
+        // Merge the sorted values of the candidates with those of the sketch
+        let (n, p) = (gcs.n() as u64, gcs.p());
+        let mut values = self
+            .candidates
+            .iter()
+            .map(|&i| (digest_value::<D>(n, p, &self.items[i]), i))
+            .collect::<Vec<_>>();
+        values.sort_unstable();
+
+        let mut sketch = gcs.iter().peekable();
+        let (mut missing, mut candidates) = (Vec::new(), Vec::new());
+        for (value, i) in values {
+            while sketch.next_if(|&other| other < value).is_some() {}
+
+            match sketch.peek().map(|other| other.cmp(&value)) {
+                Some(Ordering::Equal) => candidates.push(i),
+                _ => missing.push(i),
+            }
+        }
+
+        // Keep the items in their original order
+        missing.sort_unstable();
+        candidates.sort_unstable();
+        self.candidates = candidates;
+
+        Ok(Message::Items {
+            items: missing.into_iter().map(|i| self.items[i].clone()).collect(),
+            more: !self.candidates.is_empty(),
+        })
+    }
+}
+
+/// Returns `p` for the second round, which is increased by
+/// `SECOND_ROUND_BITS` while keeping `n * 2^p` within 64 bits.
+fn second_round_p(n: usize, p: u8) -> u8 {
+    let max = 63 - (64 - (n as u64).leading_zeros()) as u8;
+    p.saturating_add(SECOND_ROUND_BITS).min(max).max(p)
+}
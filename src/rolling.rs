@@ -0,0 +1,310 @@
+//! Sets covering a sliding window of time, made of one set per interval.
+
+use {
+    crate::{
+        approximate::{ApproximateSet, MutableApproximateSet},
+        check_parameters,
+        stats::values_false_positive_rate,
+        Error, Gcs, UnpackedGcs,
+    },
+    byteorder::{BigEndian, ReadBytesExt, WriteBytesExt},
+    digest::Digest,
+    std::{
+        collections::{vec_deque, VecDeque},
+        convert::TryFrom,
+        fs::{self, File},
+        io::{BufReader, BufWriter, Read, Write},
+        path::Path,
+    },
+};
+
+/// Magic bytes at the start of the manifest of a saved `RollingGcs`.
+const ROLLING_MAGIC: [u8; 4] = *b"GCSR";
+
+/// Name of the file describing the generations in a saved `RollingGcs`.
+const MANIFEST: &str = "manifest";
+
+/// A set of recently inserted items, made of a ring of packed generations and
+/// an active unpacked set.
+///
+/// Items are inserted into the active set, and queries check every live
+/// generation. Calling [`rotate`](RollingGcs::rotate) at the end of each
+/// interval packs the active set into a new generation and expires the oldest
+/// one, so an item is forgotten between `generations` and `generations + 1`
+/// intervals after it was last inserted. Each generation holds at most `n`
+/// items, with a false positive probability of `1 / 2^p` when full.
+///
+/// ```rust
+/// use {golomb_set::RollingGcs, twox_hash::XxHash};
+///
+/// // Forget items between 2 and 3 rotations after they were last inserted
+/// let mut gcs = RollingGcs::<XxHash>::new(100, 12, 2);
+/// assert!(gcs.insert(b"alpha").unwrap());
+/// assert!(!gcs.insert(b"alpha").unwrap());
+///
+/// gcs.rotate();
+/// gcs.rotate();
+/// assert!(gcs.contains(b"alpha"));
+///
+/// gcs.rotate();
+/// assert!(!gcs.contains(b"alpha"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct RollingGcs<D: Digest> {
+    n: usize,
+    p: u8,
+    generations: usize,
+    /// The sequence number of the active set, incremented by each rotation.
+    sequence: u64,
+    /// The packed generations, from oldest to newest.
+    packed: VecDeque<Gcs<D>>,
+    active: UnpackedGcs<D>,
+}
+
+impl<D: Digest> RollingGcs<D> {
+    /// Creates a new `RollingGcs` whose generations are created from `n` and
+    /// `p`, like [`UnpackedGcs::new`], which keeps at most `generations`
+    /// packed generations besides the active set.
+    pub fn new(n: usize, p: u8, generations: usize) -> Self {
+        Self {
+            n,
+            p,
+            generations,
+            sequence: 0,
+            // `generations` may be too large to preallocate, such as
+            // `usize::MAX` to keep every generation
+            packed: VecDeque::new(),
+            active: UnpackedGcs::new(n, p),
+        }
+    }
+
+    /// Opens a `RollingGcs` saved to `dir` by [`save`](RollingGcs::save).
+    ///
+    /// # Errors
+    /// * If there is an error reading a file in `dir`.
+    /// * If the manifest is malformed.
+    /// * If `n` or `p` are invalid, see [`Error::Parameters`].
+    /// * If a generation is not a valid set in its canonical encoding.
+    /// * If the parameters of a generation differ from those in the manifest.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self, Error> {
+        let dir = dir.as_ref();
+        let mut reader = BufReader::new(File::open(dir.join(MANIFEST))?);
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != ROLLING_MAGIC {
+            return Err(Error::Header);
+        }
+
+        let p = reader.read_u8()?;
+        let n = reader.read_u64::<BigEndian>()?;
+        check_parameters(n, p)?;
+        let n = usize::try_from(n).map_err(|_| Error::Parameters)?;
+        let generations = reader.read_u64::<BigEndian>()?;
+        let sequence = reader.read_u64::<BigEndian>()?;
+        let len = reader.read_u64::<BigEndian>()?;
+        if len > generations || len > sequence {
+            return Err(Error::Header);
+        }
+        let generations = usize::try_from(generations).map_err(|_| Error::Header)?;
+
+        let read = |sequence| -> Result<Gcs<D>, Error> {
+            let mut reader = BufReader::new(File::open(dir.join(file_name(sequence)))?);
+            let gcs = Gcs::from_canonical_reader(&mut reader)?;
+            if gcs.n != n || gcs.p != p {
+                return Err(Error::Parameters);
+            }
+            Ok(gcs)
+        };
+
+        let packed = (sequence - len..sequence)
+            .map(read)
+            .collect::<Result<VecDeque<_>, _>>()?;
+        let active = read(sequence)?.unpack();
+
+        Ok(Self {
+            n,
+            p,
+            generations,
+            sequence,
+            packed,
+            active,
+        })
+    }
+
+    /// Saves every generation to a file in `dir`, along with a manifest
+    /// describing them, and removes the files of expired generations.
+    ///
+    /// The directory must already exist, and should not be used for anything
+    /// else. Each file is written under a temporary name and then renamed, and
+    /// the manifest is replaced once every generation has been written, so an
+    /// interrupted save leaves a set which can still be opened. It contains
+    /// at least the items of the previously saved set which have not expired.
+    ///
+    /// # Errors
+    /// * If there is an error writing or removing a file in `dir`.
+    pub fn save<P: AsRef<Path>>(&self, dir: P) -> Result<(), Error> {
+        let dir = dir.as_ref();
+        let oldest = self.sequence - self.packed.len() as u64;
+
+        let write = |sequence, gcs: &Gcs<D>| -> Result<(), Error> {
+            let path = dir.join(file_name(sequence));
+            let temp = path.with_extension("gcs.tmp");
+            let mut writer = BufWriter::new(File::create(&temp)?);
+            gcs.write_canonical(&mut writer)?;
+            writer.flush()?;
+            drop(writer);
+            fs::rename(&temp, path)?;
+            Ok(())
+        };
+
+        for (sequence, gcs) in (oldest..).zip(&self.packed) {
+            write(sequence, gcs)?;
+        }
+        write(self.sequence, &self.active.pack())?;
+
+        let temp = dir.join(format!("{}.tmp", MANIFEST));
+        let mut writer = BufWriter::new(File::create(&temp)?);
+        writer.write_all(&ROLLING_MAGIC)?;
+        writer.write_u8(self.p)?;
+        writer.write_u64::<BigEndian>(self.n as u64)?;
+        writer.write_u64::<BigEndian>(self.generations as u64)?;
+        writer.write_u64::<BigEndian>(self.sequence)?;
+        writer.write_u64::<BigEndian>(self.packed.len() as u64)?;
+        writer.flush()?;
+        drop(writer);
+        fs::rename(&temp, dir.join(MANIFEST))?;
+
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let sequence = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".gcs"))
+                .and_then(|sequence| sequence.parse::<u64>().ok());
+            if let Some(sequence) = sequence {
+                if sequence < oldest || sequence > self.sequence {
+                    fs::remove_file(path)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds an entry to the active set.
+    ///
+    /// Returns whether the item was not present in any live generation. The
+    /// item is inserted into the active set either way, so that it remains
+    /// live for the following intervals.
+    ///
+    /// # Errors
+    /// * If `n` items have already been inserted into the active set.
+    pub fn insert<A: AsRef<[u8]>>(&mut self, input: A) -> Result<bool, Error> {
+        let input = input.as_ref();
+        let packed = self.packed.iter().any(|gcs| gcs.contains(input));
+
+        Ok(self.active.insert(input)? && !packed)
+    }
+
+    /// Returns whether or not an input is contained in any live generation. If
+    /// false the input is definitely not present, if true the input is
+    /// probably present.
+    pub fn contains<A: AsRef<[u8]>>(&self, input: A) -> bool {
+        let input = input.as_ref();
+
+        self.active.contains(input) || self.packed.iter().any(|gcs| gcs.contains(input))
+    }
+
+    /// Packs the active set into a new generation and replaces it with an
+    /// empty set, returning the oldest generation if it has expired.
+    pub fn rotate(&mut self) -> Option<Gcs<D>> {
+        let active = std::mem::replace(&mut self.active, UnpackedGcs::new(self.n, self.p));
+        self.packed.push_back(active.pack());
+        self.sequence += 1;
+
+        if self.packed.len() > self.generations {
+            self.packed.pop_front()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the active set, which items are inserted into.
+    pub fn active(&self) -> &UnpackedGcs<D> {
+        &self.active
+    }
+
+    /// Returns an iterator over the live packed generations, from oldest to
+    /// newest.
+    pub fn packed(&self) -> vec_deque::Iter<'_, Gcs<D>> {
+        self.packed.iter()
+    }
+
+    /// Returns the maximum number of packed generations kept besides the
+    /// active set.
+    pub fn generations(&self) -> usize {
+        self.generations
+    }
+
+    /// Returns the number of times the set has been rotated.
+    pub fn rotations(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Returns `n`, the maximum number of items in each generation.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Returns `p`, where `1 / 2^p` is the probability of a false positive in
+    /// each generation when it is full.
+    pub fn p(&self) -> u8 {
+        self.p
+    }
+}
+
+impl<D: Digest> ApproximateSet for RollingGcs<D> {
+    fn contains(&self, input: &[u8]) -> bool {
+        RollingGcs::contains(self, input)
+    }
+
+    fn len(&self) -> usize {
+        ApproximateSet::len(&self.active)
+            + self.packed.iter().map(ApproximateSet::len).sum::<usize>()
+    }
+
+    fn false_positive_rate(&self) -> f64 {
+        // A query is negative only if it is negative in every generation
+        let negative = self
+            .packed
+            .iter()
+            .map(|gcs| values_false_positive_rate(self.n, self.p, gcs.iter()))
+            .chain(Some(ApproximateSet::false_positive_rate(&self.active)))
+            .map(|rate| 1.0 - rate)
+            .product::<f64>();
+
+        1.0 - negative
+    }
+
+    fn iter_hashes(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        let mut values = self.active.values.iter().collect::<Vec<_>>();
+        for gcs in &self.packed {
+            values.extend(gcs.iter());
+        }
+        values.sort_unstable();
+
+        Box::new(values.into_iter())
+    }
+}
+
+impl<D: Digest> MutableApproximateSet for RollingGcs<D> {
+    fn insert(&mut self, input: &[u8]) -> Result<bool, Error> {
+        RollingGcs::insert(self, input)
+    }
+}
+
+/// Returns the name of the file holding the generation with a sequence number.
+fn file_name(sequence: u64) -> String {
+    format!("{}.gcs", sequence)
+}
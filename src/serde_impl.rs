@@ -0,0 +1,108 @@
+//! `Serialize` and `Deserialize` implementations, enabled by the `serde` feature.
+//!
+//! A `Gcs` is represented by its parameters, the number of values it contains
+//! and its Golomb-Rice encoded data as bytes, while an `UnpackedGcs` is
+//! represented by its parameters and the list of its values.
+
+use {
+    crate::{check_parameters, range, values::Values, DuplicatePolicy, Gcs, Lookup, UnpackedGcs},
+    digest::Digest,
+    serde::{de, Deserialize, Deserializer, Serialize, Serializer},
+    serde_bytes::ByteBuf,
+    std::marker::PhantomData,
+};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Gcs")]
+struct GcsRepr {
+    n: u64,
+    p: u8,
+    policy: DuplicatePolicy,
+    len: u64,
+    data: ByteBuf,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "UnpackedGcs")]
+struct UnpackedGcsRepr {
+    n: u64,
+    p: u8,
+    policy: DuplicatePolicy,
+    values: Vec<u64>,
+}
+
+impl<D: Digest> Serialize for Gcs<D> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GcsRepr {
+            n: self.n as u64,
+            p: self.p,
+            policy: self.policy,
+            len: self.iter().count() as u64,
+            data: ByteBuf::from(self.data.clone().into_vec()),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, D: Digest> Deserialize<'de> for Gcs<D> {
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let repr = GcsRepr::deserialize(deserializer)?;
+        check_parameters(repr.n, repr.p).map_err(de::Error::custom)?;
+
+        let mut gcs = Gcs::from_parts(repr.n as usize, repr.p, repr.len, repr.data.into_vec())
+            .map_err(de::Error::custom)?;
+        gcs.policy = repr.policy;
+
+        Ok(gcs)
+    }
+}
+
+impl<D: Digest> Serialize for UnpackedGcs<D> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        UnpackedGcsRepr {
+            n: self.n as u64,
+            p: self.p,
+            policy: self.policy,
+            values: self.values.iter().collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, D: Digest> Deserialize<'de> for UnpackedGcs<D> {
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let repr = UnpackedGcsRepr::deserialize(deserializer)?;
+        check_parameters(repr.n, repr.p).map_err(de::Error::custom)?;
+
+        if repr.values.len() as u64 > repr.n {
+            return Err(de::Error::custom(
+                "Limit for the number of elements has been exceeded",
+            ));
+        }
+
+        if let Some(&last) = repr.values.last() {
+            if last >= range(repr.n, repr.p) {
+                return Err(de::Error::custom("Value out of range for `n` and `p`"));
+            }
+        }
+
+        let ordered = match repr.policy {
+            DuplicatePolicy::Set => repr.values.windows(2).all(|w| w[0] < w[1]),
+            DuplicatePolicy::Multiset => repr.values.windows(2).all(|w| w[0] <= w[1]),
+        };
+        if !ordered {
+            return Err(de::Error::custom(
+                "Values are not sorted or contain disallowed duplicates",
+            ));
+        }
+
+        Ok(UnpackedGcs {
+            n: repr.n as usize,
+            p: repr.p,
+            policy: repr.policy,
+            lookup: Lookup::default(),
+            values: Values::from_vec(repr.n as usize, repr.p, repr.values),
+            digest: PhantomData,
+        })
+    }
+}
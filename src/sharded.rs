@@ -0,0 +1,358 @@
+//! Sets partitioned into shards which are stored in separate files.
+
+use {
+    crate::{check_parameters, hash_from_digest, reduce, Error, Gcs, UnpackedGcs},
+    byteorder::{BigEndian, ReadBytesExt, WriteBytesExt},
+    digest::Digest,
+    std::{
+        convert::TryFrom,
+        fs::{self, File},
+        io::{BufReader, BufWriter, Read, Write},
+        path::{Component, Path},
+    },
+};
+
+/// Magic bytes at the start of the manifest of a saved `ShardedGcs`.
+const SHARDED_MAGIC: [u8; 4] = *b"GCSM";
+
+/// Name of the file listing the shards of a saved `ShardedGcs`.
+const MANIFEST: &str = "manifest";
+
+/// Prefixed to a digest shorter than 16 bytes before hashing it again to
+/// route it to a shard.
+const SHARD_SALT: &[u8] = b"golomb-set shard";
+
+/// A set partitioned into shards, each of which is a [`Gcs`] with the same
+/// `n` and `p`.
+///
+/// Each item is routed to a shard by bits which are independent from those
+/// used for its value within the shard: bytes 8 to 16 of its digest when the
+/// digest is at least 16 bytes long, and otherwise the first 8 bytes of a
+/// second digest of the salted digest.
+///
+/// A sharded set is saved to a directory as one file per shard along with a
+/// manifest listing them, so each machine can build or open only the shards
+/// it is responsible for. A shard can be built like any other set, by
+/// inserting the items for which [`route`](ShardedGcs::route) returns its
+/// index.
+///
+/// ```rust
+/// use {golomb_set::ShardedGcs, md5::Md5};
+///
+/// let items = (0..1000u32).map(u32::to_be_bytes).collect::<Vec<_>>();
+/// let gcs = ShardedGcs::<Md5>::build(100, 8, 16, &items).unwrap();
+///
+/// let item = 7u32.to_be_bytes();
+/// let shard = ShardedGcs::<Md5>::route(16, &item);
+/// assert_eq!(gcs.contains(&item), Some(true));
+/// assert!(gcs.shard(shard).unwrap().contains(&item));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ShardedGcs<D: Digest> {
+    n: usize,
+    p: u8,
+    /// The file names of the shards, relative to the directory of the
+    /// manifest.
+    files: Vec<String>,
+    /// The shards which have been loaded.
+    shards: Vec<Option<Gcs<D>>>,
+}
+
+impl<D: Digest> ShardedGcs<D> {
+    /// Creates a new `ShardedGcs` of `shards` shards created from `n` and
+    /// `p`, like [`UnpackedGcs::new`], none of which are loaded.
+    ///
+    /// # Panics
+    /// * Panics if `shards == 0`.
+    /// * Panics if `n` or `p` are invalid, see [`Error::Parameters`].
+    pub fn new(n: usize, p: u8, shards: usize) -> Self {
+        assert!(shards > 0, "shards cannot be 0");
+        assert!(
+            check_parameters(n as u64, p).is_ok(),
+            "n and p must be greater than 0, and n * 2^p must fit in 64 bits"
+        );
+
+        Self {
+            n,
+            p,
+            files: (0..shards).map(file_name).collect(),
+            shards: (0..shards).map(|_| None).collect(),
+        }
+    }
+
+    /// Creates a `ShardedGcs` of `shards` shards created from `n` and `p`,
+    /// routing each input to its shard.
+    ///
+    /// # Errors
+    /// * If `n` or `p` are invalid, see [`Error::Parameters`].
+    /// * If more than `n` items would be inserted into a shard.
+    ///
+    /// # Panics
+    /// * Panics if `shards == 0`.
+    pub fn build<I, A>(n: usize, p: u8, shards: usize, inputs: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = A>,
+        A: AsRef<[u8]>,
+    {
+        check_parameters(n as u64, p)?;
+        let mut gcs = Self::new(n, p, shards);
+
+        let mut unpacked = (0..shards)
+            .map(|_| UnpackedGcs::<D>::new(n, p))
+            .collect::<Vec<_>>();
+        for input in inputs {
+            let (shard, hash) = gcs.hash(input.as_ref());
+            unpacked[shard].insert_hash(hash)?;
+        }
+
+        gcs.shards = unpacked
+            .into_iter()
+            .map(|unpacked| Some(unpacked.pack()))
+            .collect();
+        Ok(gcs)
+    }
+
+    /// Returns the index of the shard an input is routed to, out of `shards`
+    /// shards.
+    ///
+    /// # Panics
+    /// * Panics if `shards == 0`.
+    pub fn route<A: AsRef<[u8]>>(shards: usize, input: A) -> usize {
+        assert!(shards > 0, "shards cannot be 0");
+
+        shard_index::<D>(shards, &D::digest(input.as_ref()))
+    }
+
+    /// Opens the manifest of a `ShardedGcs` saved to `dir` by
+    /// [`save`](ShardedGcs::save), without loading any shards.
+    ///
+    /// # Errors
+    /// * If there is an error reading the manifest.
+    /// * If the manifest is malformed.
+    /// * If `n` or `p` are invalid, see [`Error::Parameters`].
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self, Error> {
+        let mut reader = BufReader::new(File::open(dir.as_ref().join(MANIFEST))?);
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != SHARDED_MAGIC {
+            return Err(Error::Header);
+        }
+
+        let p = reader.read_u8()?;
+        let n = reader.read_u64::<BigEndian>()?;
+        check_parameters(n, p)?;
+        let n = usize::try_from(n).map_err(|_| Error::Parameters)?;
+        let shards = usize::try_from(reader.read_u64::<BigEndian>()?)
+            .ok()
+            .filter(|&shards| shards > 0)
+            .ok_or(Error::Header)?;
+
+        let mut files = Vec::new();
+        for _ in 0..shards {
+            let mut name = vec![0u8; usize::from(reader.read_u16::<BigEndian>()?)];
+            reader.read_exact(&mut name)?;
+            let name = String::from_utf8(name).map_err(|_| Error::Header)?;
+
+            // Shards must be in the directory of the manifest
+            let mut components = Path::new(&name).components();
+            match (components.next(), components.next()) {
+                (Some(Component::Normal(_)), None) if name != MANIFEST => files.push(name),
+                _ => return Err(Error::Header),
+            }
+        }
+
+        Ok(Self {
+            n,
+            p,
+            files,
+            shards: (0..shards).map(|_| None).collect(),
+        })
+    }
+
+    /// Opens a `ShardedGcs` saved to `dir`, loading only the shards at
+    /// `indices`.
+    ///
+    /// # Errors
+    /// * If there is an error reading the manifest or a shard.
+    /// * If the manifest is malformed.
+    /// * If a shard is not a valid set in its canonical encoding.
+    /// * If the parameters of a shard differ from those in the manifest.
+    ///
+    /// # Panics
+    /// * Panics if an index is not less than the number of shards.
+    pub fn open_shards<P: AsRef<Path>>(dir: P, indices: &[usize]) -> Result<Self, Error> {
+        let dir = dir.as_ref();
+
+        let mut gcs = Self::open(dir)?;
+        for &index in indices {
+            gcs.load(dir, index)?;
+        }
+
+        Ok(gcs)
+    }
+
+    /// Loads the shard at `index` from `dir`, the directory of the manifest.
+    ///
+    /// # Errors
+    /// * If there is an error reading the shard.
+    /// * If the shard is not a valid set in its canonical encoding.
+    /// * If the parameters of the shard differ from those of the set.
+    ///
+    /// # Panics
+    /// * Panics if `index` is not less than the number of shards.
+    pub fn load<P: AsRef<Path>>(&mut self, dir: P, index: usize) -> Result<(), Error> {
+        let path = dir.as_ref().join(&self.files[index]);
+        let shard = Gcs::from_canonical_reader(&mut BufReader::new(File::open(path)?))?;
+
+        self.set_shard(index, shard)
+    }
+
+    /// Replaces the shard at `index`, such as with a set built from the items
+    /// routed to it.
+    ///
+    /// # Errors
+    /// * If the parameters of the shard differ from those of the set.
+    ///
+    /// # Panics
+    /// * Panics if `index` is not less than the number of shards.
+    pub fn set_shard(&mut self, index: usize, shard: Gcs<D>) -> Result<(), Error> {
+        if shard.n != self.n || shard.p != self.p {
+            return Err(Error::Parameters);
+        }
+
+        self.shards[index] = Some(shard);
+        Ok(())
+    }
+
+    /// Unloads the shard at `index`, returning it if it was loaded.
+    ///
+    /// # Panics
+    /// * Panics if `index` is not less than the number of shards.
+    pub fn unload(&mut self, index: usize) -> Option<Gcs<D>> {
+        self.shards[index].take()
+    }
+
+    /// Saves every loaded shard to a file in `dir`, along with a manifest
+    /// listing all of the shards and their parameters.
+    ///
+    /// Shards which are not loaded are not written, so machines responsible
+    /// for different shards can save them to a shared directory. Each file is
+    /// written under a temporary name and then renamed, so an interrupted save
+    /// never leaves a truncated shard or manifest.
+    ///
+    /// # Errors
+    /// * If there is an error writing a file in `dir`.
+    pub fn save<P: AsRef<Path>>(&self, dir: P) -> Result<(), Error> {
+        let dir = dir.as_ref();
+
+        for (name, shard) in self.files.iter().zip(&self.shards) {
+            if let Some(shard) = shard {
+                let temp = dir.join(format!("{}.tmp", name));
+                let mut writer = BufWriter::new(File::create(&temp)?);
+                shard.write_canonical(&mut writer)?;
+                writer.flush()?;
+                drop(writer);
+                fs::rename(&temp, dir.join(name))?;
+            }
+        }
+
+        let temp = dir.join(format!("{}.tmp", MANIFEST));
+        let mut writer = BufWriter::new(File::create(&temp)?);
+        writer.write_all(&SHARDED_MAGIC)?;
+        writer.write_u8(self.p)?;
+        writer.write_u64::<BigEndian>(self.n as u64)?;
+        writer.write_u64::<BigEndian>(self.files.len() as u64)?;
+        for name in &self.files {
+            writer.write_u16::<BigEndian>(name.len() as u16)?;
+            writer.write_all(name.as_bytes())?;
+        }
+        writer.flush()?;
+        drop(writer);
+        fs::rename(&temp, dir.join(MANIFEST))?;
+
+        Ok(())
+    }
+
+    /// Returns whether or not an input is contained in the set, or `None` if
+    /// the shard it is routed to is not loaded. If false the input is
+    /// definitely not present, if true the input is probably present.
+    pub fn contains<A: AsRef<[u8]>>(&self, input: A) -> Option<bool> {
+        let (shard, hash) = self.hash(input.as_ref());
+
+        self.shards[shard]
+            .as_ref()
+            .map(|gcs| gcs.contains_reduced(reduce(self.n as u64, self.p, hash)))
+    }
+
+    /// Returns the shard at `index`, if it is loaded.
+    ///
+    /// # Panics
+    /// * Panics if `index` is not less than the number of shards.
+    pub fn shard(&self, index: usize) -> Option<&Gcs<D>> {
+        self.shards[index].as_ref()
+    }
+
+    /// Returns the file name of the shard at `index`, relative to the
+    /// directory of the manifest.
+    ///
+    /// # Panics
+    /// * Panics if `index` is not less than the number of shards.
+    pub fn file_name(&self, index: usize) -> &str {
+        &self.files[index]
+    }
+
+    /// Returns the number of shards.
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Returns `n`, the maximum number of items in each shard.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Returns `p`, where `1 / 2^p` is the probability of a false positive in
+    /// each shard when it is full.
+    pub fn p(&self) -> u8 {
+        self.p
+    }
+
+    /// Returns the index of the shard an input is routed to, and its hash.
+    fn hash(&self, input: &[u8]) -> (usize, u64) {
+        let digest = D::digest(input);
+
+        (
+            shard_index::<D>(self.shards.len(), &digest),
+            hash_from_digest(&digest),
+        )
+    }
+}
+
+/// Returns the index of the shard a digest is routed to, from the high bits of
+/// the 8 bytes following those used for its value. If the digest is shorter
+/// than 16 bytes they are taken from a second digest of the salted digest.
+fn shard_index<D: Digest>(shards: usize, digest: &[u8]) -> usize {
+    let hash = if digest.len() >= 16 {
+        routing_bits(&digest[8..16])
+    } else {
+        routing_bits(&D::new().chain(SHARD_SALT).chain(digest).result())
+    };
+
+    ((u128::from(hash) * shards as u128) >> 64) as usize
+}
+
+/// Returns the first 8 bytes of a digest as an integer, or the whole digest
+/// in the high bits if it is shorter.
+fn routing_bits(digest: &[u8]) -> u64 {
+    let len = digest.len().min(8);
+    let mut buf = [0u8; 8];
+    buf[..len].copy_from_slice(&digest[..len]);
+
+    u64::from_be_bytes(buf)
+}
+
+/// Returns the default file name of the shard at `index`.
+fn file_name(index: usize) -> String {
+    format!("{}.gcs", index)
+}
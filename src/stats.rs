@@ -0,0 +1,137 @@
+//! Statistics describing the encoding of a packed set.
+
+use {
+    crate::Gcs,
+    digest::Digest,
+    std::{f64::consts::LOG2_E, fmt},
+};
+
+/// Statistics describing the contents and encoding of a [`Gcs`], returned by
+/// [`Gcs::stats`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stats {
+    /// The maximum number of items `n` which the set was created for.
+    pub n: usize,
+    /// The false positive probability parameter `p` of the set.
+    pub p: u8,
+    /// The number of values in the set, including duplicates.
+    pub len: usize,
+    /// The number of values which are equal to the previous value.
+    pub duplicates: usize,
+    /// The number of bits used by the Golomb-Rice encoding of the values.
+    pub bits: usize,
+    /// The largest difference between two consecutive values, or between zero
+    /// and the first value.
+    pub max_gap: u64,
+    /// Histogram of the lengths of the unary encoded quotients, where the
+    /// element at index `i` is the number of values with a quotient of `i`.
+    pub quotients: Vec<usize>,
+}
+
+impl Stats {
+    /// Returns the average number of bits used to encode each value, or 0 if
+    /// the set is empty.
+    pub fn bits_per_element(&self) -> f64 {
+        if self.len == 0 {
+            return 0.0;
+        }
+
+        self.bits as f64 / self.len as f64
+    }
+
+    /// Returns the expected minimum number of bits per value for Golomb-Rice
+    /// coding with modulus `2^p`, `log2(e) + p`.
+    pub fn min_bits_per_element(&self) -> f64 {
+        LOG2_E + f64::from(self.p)
+    }
+
+    /// Returns the estimated probability of a false positive, given the
+    /// number of distinct values currently in the set.
+    pub fn false_positive_rate(&self) -> f64 {
+        false_positive_rate(self.n, self.p, self.len - self.duplicates)
+    }
+}
+
+/// Returns the estimated probability of a false positive for a set with
+/// `distinct` distinct values.
+pub(crate) fn false_positive_rate(n: usize, p: u8, distinct: usize) -> f64 {
+    let range = n as f64 * 2f64.powi(i32::from(p));
+
+    1.0 - (1.0 - 1.0 / range).powf(distinct as f64)
+}
+
+/// Returns the estimated probability of a false positive for a set with the
+/// given values in ascending order.
+pub(crate) fn values_false_positive_rate<I>(n: usize, p: u8, values: I) -> f64
+where
+    I: IntoIterator<Item = u64>,
+{
+    let mut previous = None;
+    let distinct = values
+        .into_iter()
+        .filter(|&value| previous.replace(value) != Some(value))
+        .count();
+
+    false_positive_rate(n, p, distinct)
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "n: {}, p: {}", self.n, self.p)?;
+        writeln!(f, "elements: {} ({} duplicates)", self.len, self.duplicates)?;
+        writeln!(
+            f,
+            "size: {} bits ({:.3} bits per element, minimum {:.3})",
+            self.bits,
+            self.bits_per_element(),
+            self.min_bits_per_element()
+        )?;
+        writeln!(f, "max gap: {}", self.max_gap)?;
+        write!(f, "quotients:")?;
+        for (quotient, count) in self.quotients.iter().enumerate() {
+            let separator = if quotient == 0 { "" } else { "," };
+            write!(f, "{} {}: {}", separator, quotient, count)?;
+        }
+        writeln!(f)?;
+        write!(
+            f,
+            "estimated false positive rate: {:.4}%",
+            self.false_positive_rate() * 100.0
+        )
+    }
+}
+
+impl<D: Digest> Gcs<D> {
+    /// Returns statistics describing the contents and encoding of the set.
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats {
+            n: self.n,
+            p: self.p,
+            len: 0,
+            duplicates: 0,
+            bits: self.data.len(),
+            max_gap: 0,
+            quotients: Vec::new(),
+        };
+
+        let mut last = None;
+        for value in self.iter() {
+            let gap = value - last.unwrap_or(0);
+            if last.is_some() && gap == 0 {
+                stats.duplicates += 1;
+            }
+
+            let quotient = (gap >> self.p) as usize;
+            if quotient >= stats.quotients.len() {
+                stats.quotients.resize(quotient + 1, 0);
+            }
+            stats.quotients[quotient] += 1;
+
+            stats.len += 1;
+            stats.max_gap = stats.max_gap.max(gap);
+            last = Some(value);
+        }
+
+        stats
+    }
+}
@@ -0,0 +1,46 @@
+//! Utilities for measuring the behaviour of sets, enabled by the `testing` feature.
+
+use rand_core::RngCore;
+
+/// Length of the random inputs used when measuring the false positive rate.
+const INPUT_LEN: usize = 32;
+
+/// Measures the false positive rate of a set by querying it with `trials`
+/// random inputs, returning the fraction of them which it contains.
+///
+/// `contains` queries the set, which may be packed or unpacked. Each input is
+/// 32 random bytes generated by `rng`, and none of them are assumed to have
+/// been inserted into the set.
+///
+/// ```rust
+/// use {
+///     golomb_set::{testing::measure_fpr, UnpackedGcs},
+///     rand_core::SeedableRng,
+///     rand_xorshift::XorShiftRng,
+///     twox_hash::XxHash,
+/// };
+///
+/// let mut gcs = UnpackedGcs::<XxHash>::new(100, 4);
+/// gcs.extend((0..100u32).map(u32::to_be_bytes)).unwrap();
+///
+/// let mut rng = XorShiftRng::seed_from_u64(0);
+/// let fpr = measure_fpr(|input| gcs.contains(input), 10_000, &mut rng);
+/// assert!((fpr - 1.0 / 16.0).abs() < 0.02);
+/// ```
+pub fn measure_fpr<F, R>(mut contains: F, trials: usize, rng: &mut R) -> f64
+where
+    F: FnMut(&[u8]) -> bool,
+    R: RngCore,
+{
+    let mut input = [0u8; INPUT_LEN];
+    let mut positives = 0;
+
+    for _ in 0..trials {
+        rng.fill_bytes(&mut input);
+        if contains(&input) {
+            positives += 1;
+        }
+    }
+
+    positives as f64 / trials as f64
+}
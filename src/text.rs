@@ -0,0 +1,74 @@
+//! Text encodings of packed sets, for embedding them in configuration files and
+//! HTTP headers.
+
+use {
+    crate::{Error, Gcs},
+    digest::Digest,
+    std::{fmt, str::FromStr},
+};
+
+impl<D: Digest> Gcs<D> {
+    /// Returns the number of values and their Golomb-Rice encoding as
+    /// lowercase hexadecimal, in the same format as
+    /// [`write_counted`](Gcs::write_counted).
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.to_vec())
+    }
+
+    /// Creates a `Gcs` from hexadecimal text as returned by
+    /// [`to_hex`](Gcs::to_hex), with either upper or lowercase digits.
+    ///
+    /// # Errors
+    /// * If `s` is not valid hexadecimal.
+    /// * If the decoded data is not as written by [`write_counted`](Gcs::write_counted).
+    pub fn from_hex<S: AsRef<[u8]>>(s: S, n: usize, p: u8) -> Result<Self, Error> {
+        let buf = hex::decode(s).map_err(|_| Error::Hex)?;
+        Self::from_vec(buf, n, p)
+    }
+
+    /// Returns the number of values and their Golomb-Rice encoding as padded
+    /// base64 with the standard alphabet, in the same format as
+    /// [`write_counted`](Gcs::write_counted).
+    pub fn to_base64(&self) -> String {
+        base64::encode(&self.to_vec())
+    }
+
+    /// Creates a `Gcs` from base64 text as returned by
+    /// [`to_base64`](Gcs::to_base64).
+    ///
+    /// # Errors
+    /// * If `s` is not valid padded base64 with the standard alphabet.
+    /// * If the decoded data is not as written by [`write_counted`](Gcs::write_counted).
+    pub fn from_base64<S: AsRef<[u8]>>(s: S, n: usize, p: u8) -> Result<Self, Error> {
+        let buf = base64::decode(&s).map_err(|_| Error::Base64)?;
+        Self::from_vec(buf, n, p)
+    }
+
+    fn to_vec(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.write_counted(&mut buf)
+            .expect("Writing to a Vec failed");
+        buf
+    }
+}
+
+/// Formats the canonical encoding of a `Gcs` as base64, see
+/// [`Gcs::write_canonical`]. Unlike [`Gcs::to_base64`], the text includes `n`
+/// and `p`, and can be parsed back with [`str::parse`].
+impl<D: Digest> fmt::Display for Gcs<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut buf = Vec::new();
+        self.write_canonical(&mut buf)
+            .expect("Writing to a Vec failed");
+        f.write_str(&base64::encode(&buf))
+    }
+}
+
+impl<D: Digest> FromStr for Gcs<D> {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let buf = base64::decode(s).map_err(|_| Error::Base64)?;
+        Self::from_canonical_reader(&mut &buf[..])
+    }
+}
@@ -0,0 +1,188 @@
+//! Storage of the sorted values of an unpacked set, in the narrowest integer
+//! type which can hold every value in the range of the set.
+
+use {
+    crate::checked_range,
+    std::{convert::TryFrom, slice},
+};
+
+/// The sorted values of an [`UnpackedGcs`](crate::UnpackedGcs).
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Values {
+    /// Values of a set whose range `n * 2^p` is at most `2^32`.
+    Narrow(Vec<u32>),
+    /// Values of any other set.
+    Wide(Vec<u64>),
+}
+
+impl Values {
+    /// Creates storage for the values of a set with parameters `n` and `p`.
+    pub(crate) fn new(n: usize, p: u8) -> Self {
+        Self::with_capacity(n, p, 0)
+    }
+
+    /// Creates storage for the values of a set with parameters `n` and `p`,
+    /// with space for `capacity` values.
+    pub(crate) fn with_capacity(n: usize, p: u8, capacity: usize) -> Self {
+        if is_narrow(n, p) {
+            Values::Narrow(Vec::with_capacity(capacity))
+        } else {
+            Values::Wide(Vec::with_capacity(capacity))
+        }
+    }
+
+    /// Creates storage for the values of a set with parameters `n` and `p`
+    /// from sorted values, which must be less than `n * 2^p`.
+    pub(crate) fn from_vec(n: usize, p: u8, values: Vec<u64>) -> Self {
+        if is_narrow(n, p) {
+            Values::Narrow(values.into_iter().map(narrow).collect())
+        } else {
+            Values::Wide(values)
+        }
+    }
+
+    /// Returns the number of bits used to store each value.
+    pub(crate) fn bits(&self) -> u32 {
+        match self {
+            Values::Narrow(_) => 32,
+            Values::Wide(_) => 64,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Values::Narrow(values) => values.len(),
+            Values::Wide(values) => values.len(),
+        }
+    }
+
+    pub(crate) fn last(&self) -> Option<u64> {
+        match self {
+            Values::Narrow(values) => values.last().map(|&value| value.into()),
+            Values::Wide(values) => values.last().cloned(),
+        }
+    }
+
+    pub(crate) fn iter(&self) -> Iter<'_> {
+        match self {
+            Values::Narrow(values) => Iter::Narrow(values.iter()),
+            Values::Wide(values) => Iter::Wide(values.iter()),
+        }
+    }
+
+    /// Searches for a value, like [`slice::binary_search`].
+    pub(crate) fn binary_search(&self, value: u64) -> Result<usize, usize> {
+        match self {
+            Values::Narrow(values) => match u32::try_from(value) {
+                Ok(value) => values.binary_search(&value),
+                Err(_) => Err(values.len()),
+            },
+            Values::Wide(values) => values.binary_search(&value),
+        }
+    }
+
+    /// Returns the index of the first value which is not less than `value`.
+    pub(crate) fn partition_point(&self, value: u64) -> usize {
+        match self {
+            Values::Narrow(values) => values.partition_point(|&v| u64::from(v) < value),
+            Values::Wide(values) => values.partition_point(|&v| v < value),
+        }
+    }
+
+    /// Searches for a value by interpolating its position, see
+    /// [`Lookup::Interpolation`](crate::Lookup::Interpolation).
+    pub(crate) fn interpolation_search(&self, value: u64) -> bool {
+        match self {
+            Values::Narrow(values) => interpolation_search(values, value),
+            Values::Wide(values) => interpolation_search(values, value),
+        }
+    }
+
+    /// Inserts a value at `index`, which must keep the values sorted.
+    pub(crate) fn insert(&mut self, index: usize, value: u64) {
+        match self {
+            Values::Narrow(values) => values.insert(index, narrow(value)),
+            Values::Wide(values) => values.insert(index, value),
+        }
+    }
+
+    /// Appends a value, which must not be less than the last value.
+    pub(crate) fn push(&mut self, value: u64) {
+        match self {
+            Values::Narrow(values) => values.push(narrow(value)),
+            Values::Wide(values) => values.push(value),
+        }
+    }
+}
+
+/// An iterator over stored values.
+#[derive(Clone, Debug)]
+pub(crate) enum Iter<'a> {
+    Narrow(slice::Iter<'a, u32>),
+    Wide(slice::Iter<'a, u64>),
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        match self {
+            Iter::Narrow(iter) => iter.next().map(|&value| value.into()),
+            Iter::Wide(iter) => iter.next().cloned(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Iter::Narrow(iter) => iter.size_hint(),
+            Iter::Wide(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl<'a> ExactSizeIterator for Iter<'a> {}
+
+/// Returns whether or not every value less than `n * 2^p` fits in 32 bits.
+fn is_narrow(n: usize, p: u8) -> bool {
+    checked_range(n as u64, p).is_some_and(|range| range <= 1 << 32)
+}
+
+fn narrow(value: u64) -> u32 {
+    u32::try_from(value).expect("Value is within the range of the set")
+}
+
+/// The number of interpolation steps before falling back to binary search.
+const INTERPOLATION_STEPS: usize = 4;
+
+/// Returns whether or not sorted, uniformly distributed values contain
+/// `value`, by interpolating its position between the bounds of the range
+/// being searched.
+fn interpolation_search<T: Copy + Into<u64>>(values: &[T], value: u64) -> bool {
+    let (mut start, mut end) = (0, values.len());
+
+    for _ in 0..INTERPOLATION_STEPS {
+        if start >= end {
+            return false;
+        }
+
+        let (low, high) = (values[start].into(), values[end - 1].into());
+        if value < low || value > high {
+            return false;
+        }
+        if low == high {
+            return true;
+        }
+
+        let offset = u128::from(value - low) * (end - 1 - start) as u128 / u128::from(high - low);
+        let i = start + offset as usize;
+        match values[i].into().cmp(&value) {
+            std::cmp::Ordering::Equal => return true,
+            std::cmp::Ordering::Less => start = i + 1,
+            std::cmp::Ordering::Greater => end = i,
+        }
+    }
+
+    values[start..end]
+        .binary_search_by(|&other| other.into().cmp(&value))
+        .is_ok()
+}
@@ -0,0 +1,63 @@
+use {
+    golomb_set::{
+        ApproximateSet, BucketedGcs, DuplicatePolicy, EliasFanoGcs, IndexedGcs,
+        MutableApproximateSet, UnpackedGcs,
+    },
+    proptest::prelude::*,
+    twox_hash::XxHash,
+};
+
+/// Inserts the items into any mutable set.
+fn fill<S: MutableApproximateSet>(set: &mut S, items: &[u32]) {
+    for item in items {
+        set.insert(&item.to_be_bytes()).unwrap();
+    }
+}
+
+proptest! {
+    #[test]
+    fn representations_agree(items: Vec<u32>, queries: Vec<u32>, p in 1u8..16) {
+        let mut unpacked = UnpackedGcs::<XxHash>::with_policy(
+            items.len().max(1),
+            p,
+            DuplicatePolicy::Multiset,
+        );
+        fill(&mut unpacked, &items);
+        let packed = unpacked.pack();
+        let indexed = IndexedGcs::new(packed.clone(), 8);
+        let bucketed = BucketedGcs::new(&packed, 8);
+        let elias_fano = EliasFanoGcs::from(&unpacked);
+
+        let sets: [&dyn ApproximateSet; 5] = [&unpacked, &packed, &indexed, &bucketed, &elias_fano];
+        for set in &sets {
+            assert_eq!(set.len(), items.len());
+            assert_eq!(set.is_empty(), items.is_empty());
+            assert!(set.iter_hashes().eq(unpacked.iter_hashes()));
+            assert_eq!(set.false_positive_rate(), packed.stats().false_positive_rate());
+            for item in items.iter().chain(&queries) {
+                let item = item.to_be_bytes();
+                assert_eq!(set.contains(&item), unpacked.contains(item));
+            }
+        }
+    }
+}
+
+#[test]
+fn false_positive_rate() {
+    let mut gcs = UnpackedGcs::<XxHash>::new(1000, 6);
+    assert_eq!(ApproximateSet::false_positive_rate(&gcs), 0.0);
+
+    fill(&mut gcs, &(0..1000).collect::<Vec<_>>());
+    let fpr = ApproximateSet::false_positive_rate(&gcs);
+    assert!((fpr - 1.0 / 64.0).abs() < 0.001, "{}", fpr);
+}
+
+#[test]
+fn insert_limit() {
+    let mut gcs = UnpackedGcs::<XxHash>::with_policy(1, 6, DuplicatePolicy::Multiset);
+    let set: &mut dyn MutableApproximateSet = &mut gcs;
+
+    assert!(set.insert(b"a").unwrap());
+    assert!(set.insert(b"b").is_err());
+    assert_eq!(set.len(), 1);
+}
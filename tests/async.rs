@@ -0,0 +1,74 @@
+#![cfg(feature = "async")]
+
+use {
+    futures_util::stream,
+    golomb_set::{Error, Gcs, UnpackedGcs},
+    proptest::prelude::*,
+    tokio::{io::AsyncWriteExt, runtime::Runtime},
+    twox_hash::XxHash,
+};
+
+fn runtime() -> Runtime {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap()
+}
+
+proptest! {
+    #[test]
+    fn duplex_roundtrip(items: Vec<Vec<u8>>, p in 1u8..32) {
+        let n = items.len() + 1;
+        let mut gcs = UnpackedGcs::<XxHash>::new(n, p);
+        gcs.extend(&items).unwrap();
+        let gcs = gcs.pack();
+
+        let read = runtime().block_on(async {
+            // A small buffer forces the writer and reader to interleave
+            let (mut writer, mut reader) = tokio::io::duplex(16);
+            let write = async {
+                gcs.write_async(&mut writer).await.unwrap();
+                writer.shutdown().await.unwrap();
+            };
+            let read = Gcs::from_async_reader(&mut reader, n, p);
+
+            tokio::join!(write, read).1.unwrap()
+        });
+
+        assert_eq!(gcs, read);
+    }
+
+    #[test]
+    fn extend_stream(items: Vec<Vec<u8>>, p in 2u8..16) {
+        let mut expected = UnpackedGcs::<XxHash>::new(items.len(), p);
+        expected.extend(&items).unwrap();
+
+        let mut gcs = UnpackedGcs::<XxHash>::new(items.len(), p);
+        runtime()
+            .block_on(gcs.extend_stream(stream::iter(&items)))
+            .unwrap();
+
+        assert_eq!(expected, gcs);
+    }
+}
+
+#[test]
+fn extend_stream_limit() {
+    let mut gcs = UnpackedGcs::<XxHash>::new(2, 8);
+    gcs.insert(b"alpha").unwrap();
+
+    let result = runtime().block_on(gcs.extend_stream(stream::iter(vec!["beta", "gamma"])));
+
+    assert!(matches!(result, Err(Error::LimitReached)));
+    assert!(gcs.contains(b"alpha"));
+    assert!(!gcs.contains(b"beta"));
+}
+
+#[test]
+fn async_reader_invalid() {
+    let result = runtime().block_on(Gcs::<XxHash>::from_async_reader(&mut &[0xff][..], 10, 8));
+    assert!(matches!(result, Err(Error::Header)));
+
+    let buf = [0, 0, 0, 0, 0, 0, 0, 1, 0xff];
+    let result = runtime().block_on(Gcs::<XxHash>::from_async_reader(&mut &buf[..], 10, 8));
+    assert!(matches!(result, Err(Error::Decode)));
+}
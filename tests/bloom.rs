@@ -0,0 +1,124 @@
+#![cfg(feature = "bloom")]
+
+use {
+    golomb_set::{
+        bloom::{compare, BloomGcs, HybridFilter},
+        ApproximateSet, Error, UnpackedGcs,
+    },
+    twox_hash::XxHash,
+};
+
+fn unpacked(n: u32, p: u8) -> UnpackedGcs<XxHash> {
+    let mut gcs = UnpackedGcs::new(n as usize, p);
+    gcs.extend((0..n).map(u32::to_be_bytes)).unwrap();
+    gcs
+}
+
+#[test]
+fn convert() {
+    let gcs = unpacked(10_000, 8);
+    let bloom = BloomGcs::from(&gcs);
+    assert_eq!(bloom.len(), ApproximateSet::len(&gcs));
+
+    // No false negatives
+    for i in 0..10_000u32 {
+        assert!(bloom.contains(i.to_be_bytes()));
+    }
+
+    // At most about twice the false positive rate of the set
+    let positives = (10_000..110_000u32)
+        .filter(|i| bloom.contains(i.to_be_bytes()))
+        .count();
+    let fpr = positives as f64 / 100_000.0;
+    assert!(fpr < 2.5 / 256.0, "{}", fpr);
+}
+
+#[test]
+fn comparison() {
+    let gcs = unpacked(1000, 10);
+    let comparison = compare(&gcs, (0..1000u32).map(u32::to_be_bytes));
+
+    assert_eq!(gcs.bits_per_value(), 32);
+    assert_eq!(comparison.unpacked_bits, ApproximateSet::len(&gcs) * 32);
+    assert_eq!(comparison.packed_bits, gcs.pack().stats().bits);
+    // A Bloom filter needs `1.44 * log2(1 / fpr)` bits per item
+    assert!(comparison.bloom_bits > comparison.packed_bits);
+    assert!(comparison.bloom_bits < 1000 * 16);
+    assert!(comparison.to_string().contains("bloom:"));
+}
+
+#[test]
+fn hybrid() {
+    let mut filter = HybridFilter::<XxHash>::new(1000, 10, 64);
+    for i in 0..1000u32 {
+        filter.insert(i.to_be_bytes()).unwrap();
+        assert!(filter.contains(i.to_be_bytes()));
+    }
+
+    // Inserted items are found whether or not they have been folded, and
+    // colliding values are only stored once
+    let expected = unpacked(1000, 10);
+    assert!((0..1000u32).all(|i| filter.contains(i.to_be_bytes())));
+    assert_eq!(ApproximateSet::len(&filter), ApproximateSet::len(&expected));
+    assert!(filter.iter_hashes().eq(expected.iter_hashes()));
+    assert_eq!(filter.into_gcs().unwrap(), expected.pack());
+}
+
+#[test]
+fn hybrid_limit() {
+    let mut filter = HybridFilter::<XxHash>::new(100, 20, 16);
+    for i in 0..100u32 {
+        filter.insert(i.to_be_bytes()).unwrap();
+        assert_eq!(filter.pending(), (i as usize + 1) % 16);
+    }
+
+    assert!(matches!(
+        filter.insert(100u32.to_be_bytes()),
+        Err(Error::LimitReached)
+    ));
+    assert_eq!(filter.into_gcs().unwrap(), unpacked(100, 20).pack());
+}
+
+#[test]
+fn hybrid_duplicates() {
+    let mut filter = HybridFilter::<XxHash>::new(10, 8, 4);
+    assert!(filter.insert(b"alpha").unwrap());
+    assert!(!filter.insert(b"alpha").unwrap());
+    filter.fold().unwrap();
+    assert_eq!(filter.pending(), 0);
+
+    // Values which were already folded are not inserted again
+    assert!(!filter.insert(b"alpha").unwrap());
+    assert_eq!(filter.pending(), 0);
+    assert!(filter.insert(b"bravo").unwrap());
+    assert_eq!(ApproximateSet::len(&filter), 2);
+    filter.fold().unwrap();
+    assert_eq!(filter.gcs().iter().count(), 2);
+    assert!(filter.contains(b"alpha") && filter.contains(b"bravo"));
+
+    let fpr = ApproximateSet::false_positive_rate(&filter);
+    assert!((fpr - 2.0 / 2560.0).abs() < 1e-6, "{}", fpr);
+}
+
+#[test]
+fn hybrid_pending_exact() {
+    let mut filter = HybridFilter::<XxHash>::new(1000, 8, 1000);
+    for i in 0..500u32 {
+        filter.insert(i.to_be_bytes()).unwrap();
+    }
+    assert_eq!(filter.pending(), 500);
+
+    // Pending values add no false positives beyond those of the packed set
+    let mut expected = UnpackedGcs::<XxHash>::new(1000, 8);
+    expected.extend((0..500u32).map(u32::to_be_bytes)).unwrap();
+    for i in 500..10_000u32 {
+        assert_eq!(
+            filter.contains(i.to_be_bytes()),
+            expected.contains(i.to_be_bytes())
+        );
+    }
+    assert_eq!(
+        ApproximateSet::false_positive_rate(&filter),
+        ApproximateSet::false_positive_rate(&expected)
+    );
+}
@@ -0,0 +1,126 @@
+use {
+    golomb_set::{BucketedGcs, DuplicatePolicy, Error, Gcs, UnpackedGcs},
+    proptest::prelude::*,
+    twox_hash::XxHash,
+};
+
+fn packed<I: IntoIterator<Item = u32>>(n: usize, p: u8, items: I) -> Gcs<XxHash> {
+    let mut gcs = UnpackedGcs::with_policy(n, p, DuplicatePolicy::Multiset);
+    gcs.extend(items.into_iter().map(u32::to_be_bytes)).unwrap();
+    gcs.pack()
+}
+
+proptest! {
+    #[test]
+    fn matches_gcs(items: Vec<u32>, queries: Vec<u32>, p in 1u8..24, granularity in 1usize..100) {
+        let n = items.len().max(1);
+        let gcs = packed(n, p, items.iter().cloned());
+        let bucketed = BucketedGcs::new(&gcs, granularity);
+
+        assert!(bucketed.iter().eq(gcs.iter()));
+        assert_eq!(bucketed.to_gcs(), gcs);
+        for item in items.iter().chain(&queries) {
+            assert_eq!(bucketed.contains(item.to_be_bytes()), gcs.contains(item.to_be_bytes()));
+        }
+
+        let mut buf = Vec::new();
+        bucketed.write(&mut buf).unwrap();
+        let decoded = BucketedGcs::<XxHash>::from_reader(&mut &buf[..]).unwrap();
+        assert!(decoded.iter().eq(gcs.iter()));
+        let mut rewritten = Vec::new();
+        decoded.write(&mut rewritten).unwrap();
+        assert_eq!(rewritten, buf);
+    }
+}
+
+#[test]
+fn buckets() {
+    let gcs = packed(1000, 8, 0..1000);
+    let bucketed = BucketedGcs::new(&gcs, 64);
+    assert_eq!(bucketed.buckets(), 16);
+    assert_eq!((bucketed.n(), bucketed.p()), (1000, 8));
+
+    // Offsets are packed into the bits needed for the length of the data
+    let bits = gcs.stats().bits;
+    let width = 64 - (bits as u64).leading_zeros() as usize;
+    assert_eq!(bucketed.offset_bits(), 17 * width);
+}
+
+#[test]
+fn empty() {
+    let gcs = UnpackedGcs::<XxHash>::new(10, 4).pack();
+    let bucketed = BucketedGcs::new(&gcs, 4);
+    assert_eq!(bucketed.iter().count(), 0);
+    assert!(!bucketed.contains(b"item"));
+
+    let mut buf = Vec::new();
+    bucketed.write(&mut buf).unwrap();
+    assert_eq!(BucketedGcs::from_reader(&mut &buf[..]).unwrap(), bucketed);
+}
+
+#[test]
+fn invalid() {
+    let bucketed = BucketedGcs::new(&packed(100, 8, 0..100), 16);
+    let mut buf = Vec::new();
+    bucketed.write(&mut buf).unwrap();
+
+    assert!(matches!(
+        BucketedGcs::<XxHash>::from_reader(&mut &b"GCS1"[..]),
+        Err(Error::Header)
+    ));
+    assert!(BucketedGcs::<XxHash>::from_reader(&mut &buf[..buf.len() - 1]).is_err());
+
+    // Zeroing the offset table puts every value in the first bucket
+    let mut zeroed = buf.clone();
+    let table = bucketed.offset_bits().div_ceil(8);
+    for byte in &mut zeroed[22..22 + table] {
+        *byte = 0;
+    }
+    assert!(BucketedGcs::<XxHash>::from_reader(&mut &zeroed[..]).is_err());
+}
+
+#[test]
+fn corrupted_offset() {
+    let bucketed = BucketedGcs::new(&packed(100, 8, 0..100), 16);
+    let width = bucketed.offset_bits() / (bucketed.buckets() as usize + 1);
+    let middle = bucketed.buckets() as usize / 2;
+    let mut buf = Vec::new();
+    bucketed.write(&mut buf).unwrap();
+
+    // Sets every bit of the offset of the middle bucket
+    let mut corrupted = buf.clone();
+    for bit in middle * width..(middle + 1) * width {
+        corrupted[22 + bit / 8] |= 0x80 >> (bit % 8);
+    }
+    assert!(matches!(
+        BucketedGcs::<XxHash>::from_reader(&mut &corrupted[..]),
+        Err(Error::Header)
+    ));
+
+    // Clears every bit of the offset of the middle bucket
+    let mut corrupted = buf.clone();
+    for bit in middle * width..(middle + 1) * width {
+        corrupted[22 + bit / 8] &= !(0x80 >> (bit % 8));
+    }
+    assert!(matches!(
+        BucketedGcs::<XxHash>::from_reader(&mut &corrupted[..]),
+        Err(Error::Header)
+    ));
+}
+
+#[test]
+fn overflowing_offset() {
+    // A single bucket with 64 bit offsets, ending at the largest offset
+    let mut buf = b"GCSB".to_vec();
+    buf.push(8);
+    buf.extend_from_slice(&10u64.to_be_bytes());
+    buf.extend_from_slice(&1u64.to_be_bytes());
+    buf.push(64);
+    buf.extend_from_slice(&0u64.to_be_bytes());
+    buf.extend_from_slice(&u64::MAX.to_be_bytes());
+
+    assert!(matches!(
+        BucketedGcs::<XxHash>::from_reader(&mut &buf[..]),
+        Err(Error::Header)
+    ));
+}
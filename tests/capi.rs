@@ -0,0 +1,83 @@
+#![cfg(all(feature = "capi", unix))]
+
+use {
+    golomb_set::Gcs,
+    std::{env, fs, path::Path, process::Command},
+    twox_hash::XxHash,
+};
+
+const HEADER: &str = "include/golomb_set.h";
+
+/// Checks that the header matches the C interface, regenerating it instead if
+/// the `UPDATE_HEADER` environment variable is set.
+#[test]
+fn header_up_to_date() {
+    let dir = env!("CARGO_MANIFEST_DIR");
+    let config = cbindgen::Config::from_file(Path::new(dir).join("cbindgen.toml")).unwrap();
+
+    let mut header = Vec::new();
+    cbindgen::generate_with_config(dir, config)
+        .unwrap()
+        .write(&mut header);
+
+    let path = Path::new(dir).join(HEADER);
+    if env::var_os("UPDATE_HEADER").is_some() {
+        fs::write(path, header).unwrap();
+    } else {
+        assert!(
+            fs::read(path).unwrap() == header,
+            "{} is out of date, run the tests with UPDATE_HEADER=1 to regenerate it",
+            HEADER
+        );
+    }
+}
+
+#[test]
+fn c_program() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let tmp = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    // Build the static library in a separate target directory, as the one in
+    // the main target directory may have been built without the C interface
+    let target = tmp.join("capi");
+    let status = Command::new(env!("CARGO"))
+        .args([
+            "rustc",
+            "--quiet",
+            "--lib",
+            "--features",
+            "capi",
+            "--crate-type",
+            "staticlib",
+            "--target-dir",
+        ])
+        .arg(&target)
+        .current_dir(dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let lib = target.join("debug/libgolomb_set.a");
+
+    let program = tmp.join("capi_test");
+    let status = Command::new(env::var("CC").unwrap_or_else(|_| "cc".into()))
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(dir.join("include"))
+        .arg(dir.join("tests/capi/test.c"))
+        .arg(lib)
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&program)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let set = tmp.join("capi_test.gcs");
+    let status = Command::new(&program).arg(&set).status().unwrap();
+    assert!(status.success());
+
+    // The set written by the C program can be read by the library
+    let gcs = Gcs::<XxHash>::from_canonical_reader(&mut &fs::read(set).unwrap()[..]).unwrap();
+    assert!(gcs.contains(b"alpha"));
+    assert!(gcs.contains(b"beta"));
+    assert!(!gcs.contains(b"gamma"));
+}
@@ -0,0 +1,76 @@
+/* Exercises the C interface, writing a set to the file given as an argument. */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "golomb_set.h"
+
+#define CHECK(cond)                                                            \
+    do {                                                                       \
+        if (!(cond)) {                                                         \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__,   \
+                    #cond);                                                    \
+            exit(1);                                                           \
+        }                                                                      \
+    } while (0)
+
+static GcsStatus insert(GcsHandle *gcs, const char *item) {
+    return gcs_insert(gcs, (const uint8_t *)item, strlen(item));
+}
+
+static bool contains(const GcsHandle *gcs, const char *item) {
+    return gcs_contains(gcs, (const uint8_t *)item, strlen(item));
+}
+
+int main(int argc, char **argv) {
+    CHECK(argc == 2);
+
+    CHECK(gcs_new(GCS_DIGEST_XX_HASH, 0, 8) == NULL);
+    CHECK(gcs_new(GCS_DIGEST_XX_HASH, 2, 0) == NULL);
+    CHECK(gcs_new(GCS_DIGEST_MD5, 2, 64) == NULL);
+    CHECK(gcs_new(3, 2, 8) == NULL);
+
+    GcsHandle *gcs = gcs_new(GCS_DIGEST_XX_HASH, 2, 8);
+    CHECK(gcs != NULL);
+    CHECK(insert(gcs, "alpha") == GCS_STATUS_OK);
+    CHECK(insert(gcs, "beta") == GCS_STATUS_OK);
+    CHECK(insert(gcs, "gamma") == GCS_STATUS_LIMIT_REACHED);
+    CHECK(insert(NULL, "gamma") == GCS_STATUS_NULL_POINTER);
+    CHECK(contains(gcs, "alpha"));
+    CHECK(!contains(gcs, "gamma"));
+
+    CHECK(gcs_pack(gcs) == GCS_STATUS_OK);
+    CHECK(insert(gcs, "gamma") == GCS_STATUS_PACKED);
+    CHECK(contains(gcs, "alpha"));
+    CHECK(contains(gcs, "beta"));
+    CHECK(!contains(gcs, "gamma"));
+
+    size_t len = gcs_to_bytes(gcs, NULL, 0);
+    CHECK(len > 0);
+    uint8_t *buf = malloc(len);
+    CHECK(gcs_to_bytes(gcs, buf, len) == len);
+    gcs_free(gcs);
+
+    GcsHandle *read = NULL;
+    CHECK(gcs_from_bytes(GCS_DIGEST_XX_HASH, buf, len, &read) == GCS_STATUS_OK);
+    CHECK(contains(read, "alpha"));
+    CHECK(contains(read, "beta"));
+    CHECK(!contains(read, "gamma"));
+    gcs_free(read);
+
+    CHECK(gcs_from_bytes(GCS_DIGEST_XX_HASH, buf, len - 1, &read) ==
+          GCS_STATUS_INVALID_DATA);
+    CHECK(read == NULL);
+    CHECK(gcs_from_bytes(3, buf, len, &read) == GCS_STATUS_INVALID_DIGEST);
+    CHECK(read == NULL);
+    gcs_free(NULL);
+
+    FILE *file = fopen(argv[1], "wb");
+    CHECK(file != NULL);
+    CHECK(fwrite(buf, 1, len, file) == len);
+    CHECK(fclose(file) == 0);
+    free(buf);
+
+    return 0;
+}
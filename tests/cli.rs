@@ -0,0 +1,154 @@
+#![cfg(feature = "cli")]
+
+use std::{
+    env, fs,
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+fn gcs(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_gcs"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("golomb-set-cli-{}", name))
+}
+
+#[test]
+fn build_query_inspect() {
+    let set = temp_path("build_query_inspect.gcs");
+    let set = set.to_str().unwrap();
+
+    let output = gcs(&[
+        "build",
+        "--hash",
+        "md5",
+        "-p",
+        "10",
+        "-i",
+        "data/v4_uuids.txt",
+        "-o",
+        set,
+    ]);
+    assert!(output.status.success());
+
+    let output = gcs(&["inspect", set]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("n: 1000\n"));
+    assert!(stdout.contains("p: 10\n"));
+    assert!(stdout.contains("elements: 1000\n"));
+    let size = fs::metadata(set).unwrap().len();
+    assert!(stdout.contains(&format!("size: {} bytes\n", size)));
+
+    let uuids = fs::read_to_string("data/v4_uuids.txt").unwrap();
+    let mut args = vec!["query", "--hash", "md5", set];
+    args.extend(uuids.lines().take(10));
+    assert_eq!(gcs(&args).status.code(), Some(0));
+
+    args.push("not a uuid");
+    assert_eq!(gcs(&args).status.code(), Some(1));
+
+    fs::remove_file(set).unwrap();
+}
+
+#[test]
+fn convert_roundtrip() {
+    let canonical = temp_path("convert_roundtrip.gcs");
+    let canonical = canonical.to_str().unwrap();
+
+    // Rasky's Python implementation truncates MD5 hashes, so only the
+    // encoding is compared here
+    let output = gcs(&[
+        "convert",
+        "--format",
+        "raw",
+        "-n",
+        "1000",
+        "-p",
+        "10",
+        "data/v4_uuids.py.gcs",
+        "--to",
+        "canonical",
+        "-o",
+        canonical,
+    ]);
+    assert!(output.status.success());
+
+    let output = gcs(&["inspect", canonical]);
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("elements: 1000\n"));
+
+    let output = gcs(&["convert", canonical, "--to", "raw"]);
+    assert!(output.status.success());
+    assert_eq!(
+        output.stdout,
+        &include_bytes!("../data/v4_uuids.py.gcs")[..]
+    );
+
+    // Raw sets need their parameters
+    let output = gcs(&["inspect", "--format", "raw", "data/v4_uuids.py.gcs"]);
+    assert_eq!(output.status.code(), Some(2));
+
+    // Through the indexed format and back
+    let indexed = temp_path("convert_roundtrip.indexed.gcs");
+    let indexed = indexed.to_str().unwrap();
+    let output = gcs(&["convert", canonical, "--to", "indexed", "-o", indexed]);
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read(indexed).unwrap(),
+        &include_bytes!("../data/v4_uuids.indexed.gcs")[..]
+    );
+    let output = gcs(&[
+        "convert",
+        "--format",
+        "indexed",
+        indexed,
+        "--to",
+        "canonical",
+    ]);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, fs::read(canonical).unwrap());
+
+    fs::remove_file(canonical).unwrap();
+    fs::remove_file(indexed).unwrap();
+}
+
+#[test]
+fn invalid_parameters() {
+    for args in &[
+        &["build", "-n", "0", "-p", "10", "-i", "data/v4_uuids.txt"][..],
+        &["build", "-p", "0", "-i", "data/v4_uuids.txt"],
+        &["build", "-p", "64", "-i", "data/v4_uuids.txt"],
+        &["build", "-p", "60", "-i", "data/v4_uuids.txt"],
+        &[
+            "build",
+            "-p",
+            "10",
+            "--granularity",
+            "0",
+            "-i",
+            "data/v4_uuids.txt",
+        ],
+        &[
+            "inspect",
+            "--format",
+            "raw",
+            "-n",
+            "0",
+            "-p",
+            "10",
+            "data/v4_uuids.py.gcs",
+        ],
+    ] {
+        let output = gcs(args);
+        assert!(!output.status.success(), "{:?}", args);
+        assert!(!String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("panicked"));
+    }
+}
@@ -0,0 +1,72 @@
+use {
+    golomb_set::{DuplicatePolicy, Error, Gcs, UnpackedGcs},
+    proptest::prelude::*,
+    std::collections::HashSet,
+    twox_hash::XxHash,
+};
+
+fn packed<I: IntoIterator<Item = u32>>(n: usize, p: u8, items: I) -> Gcs<XxHash> {
+    let mut gcs = UnpackedGcs::with_policy(n, p, DuplicatePolicy::Multiset);
+    gcs.extend(items.into_iter().map(u32::to_be_bytes)).unwrap();
+    gcs.pack()
+}
+
+proptest! {
+    // With a large `p` collisions are unlikely, so the results are exact
+    #[test]
+    fn exact(a: HashSet<u32>, b: HashSet<u32>, duplicates: Vec<u32>) {
+        let n = a.len() + b.len() + duplicates.len() + 1;
+        let set_a = packed(n, 32, a.iter().cloned().chain(duplicates.iter().filter(|i| a.contains(i)).cloned()));
+        let set_b = packed(n, 32, b.iter().cloned());
+
+        let intersection = a.intersection(&b).count();
+        let union = a.union(&b).count();
+
+        assert_eq!(set_a.is_subset(&set_b).unwrap(), a.is_subset(&b));
+        assert_eq!(set_a.is_disjoint(&set_b).unwrap(), a.is_disjoint(&b));
+        assert_eq!(set_a.intersection_len(&set_b).unwrap(), intersection);
+        let expected = if union == 0 { 1.0 } else { intersection as f64 / union as f64 };
+        assert!((set_a.jaccard(&set_b).unwrap() - expected).abs() < 1e-6);
+
+        assert!(set_a.is_subset(&set_a).unwrap());
+        assert!((set_a.jaccard(&set_a).unwrap() - 1.0).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn jaccard_collisions() {
+    // 50000 possible values for 25000 items, so there are many collisions
+    // both within and between the sets
+    let (n, p) = (25_000, 1);
+    let a = packed(n, p, 0..15_000);
+    let b = packed(n, p, 10_000..25_000);
+
+    assert!(a.intersection_len(&b).unwrap() > 5_000 + 1_000);
+    assert!((a.jaccard(&b).unwrap() - 0.2).abs() < 0.01);
+}
+
+#[test]
+fn jaccard_full() {
+    // Together the sets contain every one of the 8 possible values
+    let reduced = |values: std::ops::Range<u64>| {
+        let mut gcs = UnpackedGcs::<XxHash>::new(4, 1);
+        for value in values {
+            gcs.insert_reduced(value).unwrap();
+        }
+        gcs.pack()
+    };
+    let (a, b) = (reduced(0..4), reduced(4..8));
+
+    let jaccard = a.jaccard(&b).unwrap();
+    assert!((0.0..=1.0).contains(&jaccard));
+}
+
+#[test]
+fn different_parameters() {
+    let a = packed(10, 8, 0..5);
+
+    let result = a.is_subset(&packed(11, 8, 0..5));
+    assert!(matches!(result, Err(Error::Parameters)));
+    let result = a.jaccard(&packed(10, 9, 0..5));
+    assert!(matches!(result, Err(Error::Parameters)));
+}
@@ -0,0 +1,168 @@
+#![cfg(feature = "dynamic")]
+
+use {
+    golomb_set::{DigestAlgorithm, DynGcs, Error, Gcs, HashAlgorithm, UnpackedGcs},
+    md5::Md5,
+    proptest::prelude::*,
+    sha1::Sha1,
+    sha2::Sha256,
+    std::convert::TryFrom,
+    twox_hash::XxHash64,
+};
+
+const ALGORITHMS: [HashAlgorithm; 6] = [
+    HashAlgorithm::Md5,
+    HashAlgorithm::Sha1,
+    HashAlgorithm::Sha256,
+    HashAlgorithm::XxHash64,
+    HashAlgorithm::SipHash,
+    HashAlgorithm::Crc32,
+];
+
+fn fnv1a(input: &[u8]) -> u64 {
+    input.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+const FNV1A: HashAlgorithm = HashAlgorithm::custom("fnv1a", fnv1a);
+
+/// Checks that a `DynGcs` behaves the same as a `Gcs<D>` with the same items.
+fn static_equivalence<D: DigestAlgorithm>(items: &[Vec<u8>], queries: &[Vec<u8>], p: u8) {
+    let mut unpacked = UnpackedGcs::<D>::new(items.len() + 1, p);
+    unpacked.extend(items).unwrap();
+    let gcs = unpacked.pack();
+
+    let dynamic = DynGcs::from_inputs(D::ALGORITHM, items.len() + 1, p, items).unwrap();
+    assert_eq!(dynamic, DynGcs::from(unpacked.pack()));
+    // Queries decode the whole set, so only check a few
+    for query in items.iter().chain(queries).step_by(8) {
+        assert_eq!(gcs.contains(query), dynamic.contains(query));
+    }
+
+    assert!(gcs.iter().eq(Gcs::<D>::try_from(dynamic).unwrap().iter()));
+}
+
+proptest! {
+    #[test]
+    fn equivalence(items: Vec<Vec<u8>>, queries: Vec<Vec<u8>>, p in 1u8..16) {
+        static_equivalence::<Md5>(&items, &queries, p);
+        static_equivalence::<Sha1>(&items, &queries, p);
+        static_equivalence::<Sha256>(&items, &queries, p);
+        static_equivalence::<XxHash64>(&items, &queries, p);
+    }
+
+    #[test]
+    fn contains(items: Vec<Vec<u8>>, p in 1u8..16) {
+        for &algorithm in ALGORITHMS.iter().chain(&[FNV1A]) {
+            let gcs = DynGcs::from_inputs(algorithm, items.len() + 1, p, &items).unwrap();
+
+            assert_eq!(gcs.algorithm(), algorithm);
+            for item in items.iter().step_by(8) {
+                assert!(gcs.contains(item));
+                assert_eq!(gcs.count(item), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn roundtrip(items: Vec<Vec<u8>>, p in 1u8..16) {
+        for &algorithm in &ALGORITHMS {
+            let gcs = DynGcs::from_inputs(algorithm, items.len() + 1, p, &items).unwrap();
+
+            let mut buf = Vec::new();
+            gcs.write(&mut buf).unwrap();
+            let read = DynGcs::from_reader(&mut &buf[..], items.len() + 1, p).unwrap();
+            assert_eq!(gcs, read);
+
+            let mut buf = Vec::new();
+            gcs.write_canonical(&mut buf).unwrap();
+            let read = DynGcs::from_canonical_reader(&mut &buf[..]).unwrap();
+            assert_eq!(gcs, read);
+        }
+    }
+}
+
+#[test]
+fn names() {
+    for &algorithm in &ALGORITHMS {
+        assert_eq!(algorithm, algorithm.to_string().parse().unwrap());
+    }
+
+    assert!(matches!(
+        "fnv1a".parse::<HashAlgorithm>(),
+        Err(Error::Algorithm)
+    ));
+    FNV1A.register();
+    FNV1A.register();
+    assert_eq!(FNV1A, "fnv1a".parse().unwrap());
+    assert!(matches!(
+        "fnv".parse::<HashAlgorithm>(),
+        Err(Error::Algorithm)
+    ));
+}
+
+#[test]
+#[should_panic(expected = "already registered")]
+fn register_conflict() {
+    fn other(_: &[u8]) -> u64 {
+        0
+    }
+
+    HashAlgorithm::custom("conflict", fnv1a).register();
+    HashAlgorithm::custom("conflict", other).register();
+}
+
+#[test]
+#[should_panic(expected = "built in")]
+fn register_built_in() {
+    HashAlgorithm::custom("md5", fnv1a).register();
+}
+
+#[test]
+fn mismatched_conversion() {
+    let gcs = DynGcs::from_inputs(HashAlgorithm::Md5, 2, 8, ["alpha", "beta"]).unwrap();
+
+    assert!(matches!(
+        Gcs::<Sha1>::try_from(gcs.clone()),
+        Err(Error::Algorithm)
+    ));
+    assert!(Gcs::<Md5>::try_from(gcs).is_ok());
+}
+
+#[test]
+fn limit() {
+    let result = DynGcs::from_inputs(HashAlgorithm::Crc32, 1, 8, ["alpha", "beta"]);
+
+    assert!(matches!(result, Err(Error::LimitReached)));
+}
+
+#[test]
+fn invalid_parameters() {
+    for &(n, p) in &[(0, 8), (2, 0), (2, 64), (1 << 8, 60)] {
+        let result = DynGcs::from_inputs(HashAlgorithm::Crc32, n, p, ["alpha"]);
+        assert!(matches!(result, Err(Error::Parameters)));
+    }
+}
+
+#[test]
+fn read_custom() {
+    fn reversed(input: &[u8]) -> u64 {
+        fnv1a(input).swap_bytes()
+    }
+
+    let algorithm = HashAlgorithm::custom("reversed-fnv1a", reversed);
+    let gcs = DynGcs::from_inputs(algorithm, 2, 8, ["alpha", "beta"]).unwrap();
+    let mut buf = Vec::new();
+    gcs.write_canonical(&mut buf).unwrap();
+
+    // Custom algorithms can only be read once registered
+    assert!(matches!(
+        DynGcs::from_canonical_reader(&mut &buf[..]),
+        Err(Error::Algorithm)
+    ));
+    algorithm.register();
+    let read = DynGcs::from_canonical_reader(&mut &buf[..]).unwrap();
+    assert_eq!(read.algorithm(), algorithm);
+    assert!(read.contains("alpha"));
+}
@@ -0,0 +1,63 @@
+use {
+    golomb_set::{DuplicatePolicy, EliasFanoGcs, UnpackedGcs},
+    proptest::prelude::*,
+    twox_hash::XxHash,
+};
+
+proptest! {
+    #[test]
+    fn matches_unpacked(
+        items: Vec<u32>,
+        duplicates: Vec<u32>,
+        queries: Vec<u32>,
+        p in 1u8..40,
+    ) {
+        let n = (items.len() + duplicates.len()).max(1);
+        let mut unpacked = UnpackedGcs::<XxHash>::with_policy(n, p, DuplicatePolicy::Multiset);
+        unpacked.extend(items.iter().chain(&duplicates).map(|i| i.to_be_bytes())).unwrap();
+
+        let gcs = EliasFanoGcs::from(&unpacked);
+        let packed = unpacked.pack();
+        assert!(gcs.iter().eq(packed.iter()));
+        assert_eq!(gcs.len(), packed.iter().count());
+        assert_eq!(gcs.get(gcs.len()), None);
+        for item in items.iter().chain(&queries) {
+            assert_eq!(gcs.contains(item.to_be_bytes()), unpacked.contains(item.to_be_bytes()));
+        }
+
+        assert_eq!(gcs.unpack(), unpacked);
+        assert_eq!(gcs.pack(), packed);
+        assert_eq!(EliasFanoGcs::from(&packed), gcs);
+    }
+}
+
+#[test]
+fn large() {
+    let mut unpacked = UnpackedGcs::<XxHash>::new(100_000, 10);
+    unpacked
+        .extend((0..100_000u32).map(u32::to_be_bytes))
+        .unwrap();
+    let gcs = EliasFanoGcs::from(&unpacked);
+
+    let values = unpacked.pack().iter().collect::<Vec<_>>();
+    for (i, &value) in values.iter().enumerate().step_by(97) {
+        assert_eq!(gcs.get(i), Some(value));
+        assert!(gcs.contains_reduced(value));
+    }
+    for i in (0..100_000u32).step_by(101) {
+        assert!(gcs.contains(i.to_be_bytes()));
+    }
+
+    // About `p + 2` bits per value
+    let bits_per_value = gcs.bits() as f64 / gcs.len() as f64;
+    assert!(bits_per_value < 12.5, "{}", bits_per_value);
+}
+
+#[test]
+fn empty() {
+    let gcs = EliasFanoGcs::from(&UnpackedGcs::<XxHash>::new(8, 8));
+    assert!(gcs.is_empty());
+    assert_eq!(gcs.get(0), None);
+    assert!(!gcs.contains(b"item"));
+    assert!(!gcs.contains_reduced(0));
+}
@@ -0,0 +1,54 @@
+use {
+    golomb_set::{ExternalGcsBuilder, Gcs, UnpackedGcs},
+    std::{env, fs},
+    twox_hash::XxHash,
+};
+
+#[test]
+fn external_matches_unpacked() {
+    let dir = env::temp_dir().join("golomb-set-external-matches-unpacked");
+    fs::create_dir_all(&dir).unwrap();
+
+    // Includes duplicates, which must only be stored once
+    let items = (0u32..20_000)
+        .map(|i| (i % 15_000).to_be_bytes())
+        .collect::<Vec<_>>();
+
+    // Spills a run every 128 values
+    let mut builder = ExternalGcsBuilder::<XxHash>::with_temp_dir(15_000, 12, 1024, &dir);
+    for item in &items {
+        builder.insert(item).unwrap();
+    }
+    assert!(fs::read_dir(&dir).unwrap().count() > 100);
+
+    let mut external = Vec::new();
+    let len = builder.finish(&mut external).unwrap();
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+    let mut unpacked = UnpackedGcs::<XxHash>::new(15_000, 12);
+    unpacked.extend(&items).unwrap();
+    assert_eq!(len, unpacked.pack().iter().count());
+
+    let mut expected = Vec::new();
+    unpacked.pack().write_canonical(&mut expected).unwrap();
+
+    assert_eq!(external, expected);
+    assert_eq!(
+        Gcs::<XxHash>::from_canonical_reader(&mut &external[..]).unwrap(),
+        unpacked.pack()
+    );
+
+    fs::remove_dir(&dir).unwrap();
+}
+
+#[test]
+fn external_limit() {
+    let mut builder = ExternalGcsBuilder::<XxHash>::new(10, 8, 64);
+    for i in 0u32..11 {
+        builder.insert(i.to_be_bytes()).unwrap();
+    }
+
+    let mut buf = Vec::new();
+    assert!(builder.finish(&mut buf).is_err());
+    assert!(buf.is_empty());
+}
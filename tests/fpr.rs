@@ -0,0 +1,158 @@
+#![cfg(feature = "testing")]
+
+use {
+    digest::{
+        generic_array::{
+            typenum::{U2, U4},
+            ArrayLength, GenericArray,
+        },
+        Digest,
+    },
+    golomb_set::{testing::measure_fpr, UnpackedGcs},
+    md5::Md5,
+    rand_core::SeedableRng,
+    rand_xorshift::XorShiftRng,
+    sha1::Sha1,
+    std::marker::PhantomData,
+    twox_hash::XxHash,
+};
+
+const TRIALS: usize = 10_000;
+
+/// MD5 truncated to its last `N` bytes.
+struct Md5Trunc<N>(Md5, PhantomData<N>);
+
+impl<N: ArrayLength<u8>> Md5Trunc<N> {
+    fn truncate(digest: GenericArray<u8, <Md5 as Digest>::OutputSize>) -> GenericArray<u8, N> {
+        GenericArray::clone_from_slice(&digest[16 - N::to_usize()..])
+    }
+}
+
+impl<N: ArrayLength<u8>> Digest for Md5Trunc<N> {
+    type OutputSize = N;
+
+    fn new() -> Self {
+        Md5Trunc(Md5::new(), PhantomData)
+    }
+
+    fn input<B: AsRef<[u8]>>(&mut self, data: B) {
+        self.0.input(data);
+    }
+
+    fn chain<B: AsRef<[u8]>>(self, data: B) -> Self {
+        Md5Trunc(self.0.chain(data), PhantomData)
+    }
+
+    fn result(self) -> GenericArray<u8, N> {
+        Self::truncate(self.0.result())
+    }
+
+    fn result_reset(&mut self) -> GenericArray<u8, N> {
+        Self::truncate(self.0.result_reset())
+    }
+
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+
+    fn output_size() -> usize {
+        N::to_usize()
+    }
+
+    fn digest(data: &[u8]) -> GenericArray<u8, N> {
+        Self::truncate(Md5::digest(data))
+    }
+}
+
+/// Checks that a full set contains the expected number of distinct values, and
+/// that its measured false positive rate is within 5 standard deviations of
+/// the rate expected from them, which approaches `1/2^p`.
+fn check_fpr<D: Digest>(n: usize, p: u8) {
+    let unpacked = {
+        let mut unpacked = UnpackedGcs::<D>::new(n, p);
+        unpacked
+            .extend((0..n as u32).map(u32::to_be_bytes))
+            .unwrap();
+        unpacked
+    };
+    let packed = unpacked.pack();
+    let stats = packed.stats();
+
+    // Items colliding with each other are only stored once, the number of
+    // collisions is approximately Poisson distributed
+    let range = n as f64 * 2f64.powi(i32::from(p));
+    let collisions = n as f64 - range * (1.0 - (1.0 - 1.0 / range).powf(n as f64));
+    assert!(
+        ((n - stats.len) as f64) < collisions + 5.0 * collisions.sqrt() + 1.0,
+        "n: {}, p: {}, collisions: {}, expected: {}",
+        n,
+        p,
+        n - stats.len,
+        collisions
+    );
+
+    let mut rng = XorShiftRng::seed_from_u64(n as u64 * u64::from(p));
+    let mut measured = vec![measure_fpr(
+        |input| unpacked.contains(input),
+        TRIALS,
+        &mut rng,
+    )];
+
+    // Querying packed sets is slow, so they are only checked when small
+    if n <= 100 {
+        measured.push(measure_fpr(
+            |input| packed.contains(input),
+            TRIALS,
+            &mut rng,
+        ));
+    }
+
+    let expected = stats.false_positive_rate();
+    let deviation = (expected * (1.0 - expected) / TRIALS as f64).sqrt();
+    for measured in measured {
+        assert!(
+            (measured - expected).abs() < 5.0 * deviation,
+            "n: {}, p: {}, expected: {}, measured: {}",
+            n,
+            p,
+            expected,
+            measured
+        );
+    }
+}
+
+fn check_grid<D: Digest>() {
+    for &n in &[100, 1000, 10_000] {
+        for &p in &[3, 5, 8] {
+            check_fpr::<D>(n, p);
+        }
+    }
+}
+
+#[test]
+fn fpr_xxhash() {
+    check_grid::<XxHash>();
+}
+
+#[test]
+fn fpr_md5() {
+    check_grid::<Md5>();
+}
+
+#[test]
+fn fpr_sha1() {
+    check_grid::<Sha1>();
+}
+
+#[test]
+fn fpr_md5_trunc_32() {
+    check_grid::<Md5Trunc<U4>>();
+}
+
+#[test]
+fn fpr_md5_trunc_16() {
+    // `n * 2^p` must fit within the 16 bits of the digest
+    check_fpr::<Md5Trunc<U2>>(100, 3);
+    check_fpr::<Md5Trunc<U2>>(100, 5);
+    check_fpr::<Md5Trunc<U2>>(200, 8);
+}
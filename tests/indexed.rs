@@ -0,0 +1,60 @@
+// `data/v4_uuids.indexed.gcs` is the set in `data/v4_uuids.py.gcs` written by `IndexedGcs` with an
+// index entry every 64 values, checking that the encoding of this crate's indexed format does not
+// change.
+
+use {
+    golomb_set::{Gcs, IndexedGcs, UnpackedGcs},
+    md5::Md5,
+    proptest::prelude::*,
+    twox_hash::XxHash,
+};
+
+fn py() -> Gcs<Md5> {
+    Gcs::from_reader(
+        &mut &include_bytes!("../data/v4_uuids.py.gcs")[..],
+        1000,
+        10,
+    )
+    .unwrap()
+}
+
+proptest! {
+    #[test]
+    fn indexed_contains(items: Vec<u32>, queries: Vec<u32>, p in 8u8..24, granularity in 1usize..16) {
+        let mut unpacked = UnpackedGcs::<XxHash>::new(items.len() + 1, p);
+        unpacked.extend(items.iter().map(|item| item.to_be_bytes())).unwrap();
+        let gcs = unpacked.pack();
+        let indexed = IndexedGcs::new(gcs.clone(), granularity);
+
+        let mut buf = Vec::new();
+        indexed.write(&mut buf).unwrap();
+        let read = IndexedGcs::<XxHash>::from_reader(&mut &buf[..]).unwrap();
+        assert_eq!(read, indexed);
+
+        for item in items.iter().chain(&queries) {
+            assert_eq!(read.contains(item.to_be_bytes()), gcs.contains(item.to_be_bytes()));
+        }
+    }
+}
+
+#[test]
+fn indexed_fixture() {
+    let mut buf = Vec::new();
+    IndexedGcs::new(py(), 64).write(&mut buf).unwrap();
+    assert_eq!(buf, &include_bytes!("../data/v4_uuids.indexed.gcs")[..]);
+
+    let gcs =
+        IndexedGcs::<Md5>::from_reader(&mut &include_bytes!("../data/v4_uuids.indexed.gcs")[..])
+            .unwrap();
+    assert_eq!(gcs.index().len(), 16);
+    assert!(gcs.gcs().iter().eq(py().iter()));
+}
+
+#[test]
+fn indexed_invalid_index() {
+    let mut buf = include_bytes!("../data/v4_uuids.indexed.gcs").to_vec();
+
+    // Move the position of the second index entry by a bit
+    buf[48] += 1;
+    assert!(IndexedGcs::<Md5>::from_reader(&mut &buf[..]).is_err());
+}
@@ -3,7 +3,241 @@ extern crate doc_comment;
 
 doctest!("../README.md");
 
-use {golomb_set::UnpackedGcs, proptest::prelude::*, twox_hash::XxHash};
+use {
+    golomb_set::{DuplicatePolicy, Error, Gcs, Lookup, UnpackedGcs},
+    proptest::{collection::vec, prelude::*},
+    rand_core::{RngCore, SeedableRng},
+    rand_xorshift::XorShiftRng,
+    sha1::{Digest, Sha1},
+    std::convert::TryInto,
+    twox_hash::XxHash,
+};
+
+fn shuffled_items() -> impl Strategy<Value = (Vec<Vec<u8>>, Vec<Vec<u8>>)> {
+    vec(any::<Vec<u8>>(), 0..100)
+        .prop_flat_map(|items| (Just(items.clone()), Just(items).prop_shuffle()))
+}
+
+// Uses the same set as `examples/efficiency.rs`
+#[test]
+fn stats_efficiency() {
+    let mut gcs = UnpackedGcs::<XxHash>::new(1000, 7);
+    let mut prng = XorShiftRng::seed_from_u64(0);
+    for _ in 0..1000 {
+        let mut buf = [0u8; 32];
+        prng.fill_bytes(&mut buf);
+        gcs.insert(buf).unwrap();
+    }
+    let gcs = gcs.pack();
+
+    let mut buf = Vec::new();
+    gcs.write(&mut buf).unwrap();
+    let stats = gcs.stats();
+
+    assert_eq!(buf.len(), 1069);
+    assert_eq!(stats.bits.div_ceil(8), buf.len());
+    assert_eq!(stats.len, gcs.iter().count());
+    assert_eq!(stats.duplicates, 0);
+    assert_eq!(stats.quotients.iter().sum::<usize>(), stats.len);
+    assert_eq!(stats.max_gap >> 7, stats.quotients.len() as u64 - 1);
+
+    // The theoretical minimum in the example is 875 bytes, or 7 bits per element
+    assert!(stats.bits_per_element() > 7.0);
+    assert!(stats.bits_per_element() > stats.min_bits_per_element());
+    assert!(stats.bits_per_element() < stats.min_bits_per_element() + 0.5);
+
+    assert!((stats.false_positive_rate() - 1.0 / 128.0).abs() < 0.001);
+}
+
+#[test]
+fn stats_duplicates() {
+    let mut gcs = UnpackedGcs::<XxHash>::with_policy(4, 7, DuplicatePolicy::Multiset);
+    for item in &["alpha", "alpha", "alpha", "bravo"] {
+        gcs.insert(item).unwrap();
+    }
+    let stats = gcs.pack().stats();
+
+    assert_eq!(stats.len, 4);
+    assert_eq!(stats.duplicates, 2);
+    // Duplicates are encoded as a difference of zero
+    assert!(stats.quotients[0] >= 2);
+    assert_eq!(stats.quotients.iter().sum::<usize>(), 4);
+}
+
+#[test]
+fn stats_empty() {
+    let stats = UnpackedGcs::<XxHash>::new(10, 8).pack().stats();
+
+    assert_eq!(stats.len, 0);
+    assert_eq!(stats.bits_per_element(), 0.0);
+    assert!(stats.to_string().contains("(0.000 bits per element"));
+}
+
+#[test]
+fn insert_reduced_range() {
+    let mut gcs = UnpackedGcs::<Sha1>::new(10, 4);
+
+    assert!(gcs.insert_reduced(159).unwrap());
+    assert!(matches!(gcs.insert_reduced(160), Err(Error::OutOfRange)));
+    assert!(gcs.contains_reduced(159));
+    assert!(!gcs.contains_reduced(160));
+    assert!(gcs.pack().contains_reduced(159));
+}
+
+#[test]
+fn write_multiset_small_p() {
+    for p in 1..8 {
+        let mut gcs = UnpackedGcs::<XxHash>::with_policy(3, p, DuplicatePolicy::Multiset);
+        for _ in 0..3 {
+            gcs.insert(b"x").unwrap();
+        }
+        let gcs = gcs.pack();
+
+        let mut buf = Vec::new();
+        gcs.write_counted(&mut buf).unwrap();
+        let read = Gcs::<XxHash>::from_counted_reader(&mut &buf[..], 3, p).unwrap();
+        assert_eq!(read.count(b"x"), 3);
+        assert!(read.iter().eq(gcs.iter()));
+        let read = Gcs::<XxHash>::from_hex(gcs.to_hex(), 3, p).unwrap();
+        assert_eq!(read.count(b"x"), 3);
+
+        // The raw format cannot always distinguish the duplicates from padding
+        let mut raw = Vec::new();
+        match gcs.write(&mut raw) {
+            Ok(()) => {
+                let read = Gcs::<XxHash>::from_reader(&mut &raw[..], 3, p).unwrap();
+                assert_eq!(read.count(b"x"), 3);
+            }
+            Err(Error::Padding) => assert!(p < 7),
+            Err(err) => panic!("{}", err),
+        }
+    }
+}
+
+#[test]
+fn read_policy() {
+    let mut gcs = UnpackedGcs::<XxHash>::with_policy(10, 8, DuplicatePolicy::Multiset);
+    gcs.extend(["alpha", "alpha", "beta"]).unwrap();
+    let gcs = gcs.pack();
+
+    // Sets with duplicates are read as multisets
+    let mut buf = Vec::new();
+    gcs.write_counted(&mut buf).unwrap();
+    let read = Gcs::<XxHash>::from_counted_reader(&mut &buf[..], 10, 8).unwrap();
+    assert_eq!(read, gcs);
+    assert_eq!(read.unpack().policy(), DuplicatePolicy::Multiset);
+    assert_eq!(read.count(b"alpha"), 2);
+
+    // The canonical encoding has no duplicates, and equality ignores the policy
+    let mut unique = UnpackedGcs::<XxHash>::with_policy(10, 8, DuplicatePolicy::Multiset);
+    unique.extend(["alpha", "beta"]).unwrap();
+    let unique = unique.pack();
+    let mut buf = Vec::new();
+    unique.write_canonical(&mut buf).unwrap();
+    let read = Gcs::<XxHash>::from_canonical_reader(&mut &buf[..]).unwrap();
+    assert_eq!(read, unique);
+    assert_eq!(read.unpack().policy(), DuplicatePolicy::Set);
+}
+
+#[test]
+fn read_invalid_parameters() {
+    let mut buf = Vec::new();
+    UnpackedGcs::<XxHash>::new(10, 8)
+        .pack()
+        .write_canonical(&mut buf)
+        .unwrap();
+
+    // n = 0, p = 0, p = 64 and n * 2^p overflowing
+    for &(n, p) in &[(0u64, 8u8), (10, 0), (10, 64), (1 << 40, 40)] {
+        buf[4] = p;
+        buf[5..13].copy_from_slice(&n.to_be_bytes());
+        assert!(matches!(
+            Gcs::<XxHash>::from_canonical_reader(&mut &buf[..]),
+            Err(Error::Parameters)
+        ));
+        assert!(matches!(
+            Gcs::<XxHash>::from_counted_reader(&mut &[0; 8][..], n as usize, p),
+            Err(Error::Parameters)
+        ));
+    }
+}
+
+#[test]
+fn read_out_of_range() {
+    let mut gcs = UnpackedGcs::<XxHash>::new(100, 8);
+    gcs.insert_reduced((100 << 8) - 1).unwrap();
+    let gcs = gcs.pack();
+
+    // The value is only in range for the n it was written with
+    let mut buf = Vec::new();
+    gcs.write_canonical(&mut buf).unwrap();
+    buf[5..13].copy_from_slice(&10u64.to_be_bytes());
+    assert!(matches!(
+        Gcs::<XxHash>::from_canonical_reader(&mut &buf[..]),
+        Err(Error::OutOfRange)
+    ));
+
+    let mut buf = Vec::new();
+    gcs.write_counted(&mut buf).unwrap();
+    assert!(matches!(
+        Gcs::<XxHash>::from_counted_reader(&mut &buf[..], 10, 8),
+        Err(Error::OutOfRange)
+    ));
+
+    let mut buf = Vec::new();
+    gcs.write(&mut buf).unwrap();
+    assert!(matches!(
+        Gcs::<XxHash>::from_reader(&mut &buf[..], 10, 8),
+        Err(Error::OutOfRange)
+    ));
+}
+
+#[test]
+fn read_overflow() {
+    // n = 1, p = 63 and a single value with a quotient of 2, which is 2^64
+    let mut buf = b"GCS1".to_vec();
+    buf.push(63);
+    buf.extend_from_slice(&1u64.to_be_bytes());
+    buf.extend_from_slice(&1u64.to_be_bytes());
+    buf.extend_from_slice(&[0b1100_0000, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+    assert!(matches!(
+        Gcs::<XxHash>::from_canonical_reader(&mut &buf[..]),
+        Err(Error::Decode)
+    ));
+}
+
+#[test]
+fn lookup_equality() {
+    let mut gcs = UnpackedGcs::<Sha1>::new(10, 8);
+    gcs.insert(b"alpha").unwrap();
+
+    let mut interpolated = gcs.clone();
+    interpolated.set_lookup(Lookup::Interpolation);
+    assert_eq!(interpolated, gcs);
+
+    interpolated.insert(b"beta").unwrap();
+    assert_ne!(interpolated, gcs);
+}
+
+#[test]
+fn storage_width() {
+    assert_eq!(UnpackedGcs::<XxHash>::new(1000, 20).bits_per_value(), 32);
+    assert_eq!(UnpackedGcs::<XxHash>::new(1 << 12, 20).bits_per_value(), 32);
+    assert_eq!(
+        UnpackedGcs::<XxHash>::new((1 << 12) + 1, 20).bits_per_value(),
+        64
+    );
+    assert_eq!(UnpackedGcs::<XxHash>::new(1000, 40).bits_per_value(), 64);
+    assert_eq!(UnpackedGcs::<XxHash>::new(1000, 63).bits_per_value(), 64);
+
+    // The largest value of a narrow set still fits
+    let mut gcs = UnpackedGcs::<XxHash>::new(1, 32);
+    assert!(gcs.insert_reduced(u64::from(u32::MAX)).unwrap());
+    assert!(gcs.contains_reduced(u64::from(u32::MAX)));
+    assert!(!gcs.contains_reduced(1 << 32));
+    assert_eq!(gcs.pack().unpack(), gcs);
+}
 
 proptest! {
     #[test]
@@ -70,4 +304,158 @@ proptest! {
 
         assert_eq!(gcs, gcs.pack().unpack());
     }
+
+    #[test]
+    fn duplicates_set(bytes: Vec<u8>, p in 2u8..16) {
+        let mut gcs = UnpackedGcs::<XxHash>::new(1, p);
+
+        assert!(gcs.insert(&bytes).unwrap());
+        assert!(!gcs.insert(&bytes).unwrap());
+        assert_eq!(gcs.count(&bytes), 1);
+        assert_eq!(gcs.pack().count(&bytes), 1);
+    }
+
+    #[test]
+    fn duplicates_multiset(a: Vec<u8>, b: Vec<u8>, times in 1usize..20, p in 20u8..32) {
+        if a == b {
+            return Ok(());
+        }
+
+        let mut gcs = UnpackedGcs::<XxHash>::with_policy(times + 1, p, DuplicatePolicy::Multiset);
+        for i in 0..times {
+            assert_eq!(gcs.insert(&a).unwrap(), i == 0);
+        }
+        gcs.insert(&b).unwrap();
+
+        assert!(gcs.insert(&a).is_err());
+        assert_eq!(gcs.count(&a), times);
+        assert_eq!(gcs.count(&b), 1);
+
+        let packed = gcs.pack();
+        assert_eq!(packed.count(&a), times);
+        assert_eq!(packed.count(&b), 1);
+        assert_eq!(gcs, packed.unpack());
+    }
+
+    // Sets with the same members must have identical canonical encodings
+    #[test]
+    fn canonical_encoding((items, shuffled) in shuffled_items(), p in 2u8..16) {
+        let n = items.len() * 2 + 1;
+
+        let set = {
+            let mut unpacked = UnpackedGcs::<XxHash>::new(n, p);
+            for item in &items {
+                unpacked.insert(item).unwrap();
+            }
+            unpacked.pack()
+        };
+
+        let multiset = {
+            let mut unpacked = UnpackedGcs::<XxHash>::with_policy(n, p, DuplicatePolicy::Multiset);
+            for item in shuffled.iter().chain(&items) {
+                unpacked.insert(item).unwrap();
+            }
+            unpacked.pack().unpack().pack()
+        };
+
+        let read = {
+            let mut buf = Vec::new();
+            set.write_counted(&mut buf).unwrap();
+            Gcs::<XxHash>::from_counted_reader(&mut &buf[..], n, p).unwrap()
+        };
+
+        let mut canonical = Vec::new();
+        set.write_canonical(&mut canonical).unwrap();
+        let canonical_read = Gcs::<XxHash>::from_canonical_reader(&mut &canonical[..]).unwrap();
+
+        for other in &[multiset, read, canonical_read] {
+            let mut buf = Vec::new();
+            other.write_canonical(&mut buf).unwrap();
+            assert_eq!(canonical, buf);
+            assert_eq!(set.content_hash::<Sha1>(), other.content_hash::<Sha1>());
+        }
+    }
+
+    // Pre-computed hashes must behave like the inputs they are the hash of
+    #[test]
+    fn insert_hash(items: Vec<Vec<u8>>, queries: Vec<Vec<u8>>, p in 2u8..16) {
+        let n = items.len() + 1;
+        let hash = |input: &Vec<u8>| u64::from_be_bytes(XxHash::digest(input)[..8].try_into().unwrap());
+
+        let mut hashed = UnpackedGcs::<XxHash>::new(n, p);
+        let mut unhashed = UnpackedGcs::<XxHash>::new(n, p);
+        for item in &items {
+            assert_eq!(hashed.insert(item).unwrap(), unhashed.insert_hash(hash(item)).unwrap());
+        }
+        assert_eq!(hashed, unhashed);
+
+        let packed = hashed.pack();
+        for query in items.iter().chain(&queries).step_by(8) {
+            assert_eq!(hashed.contains(query), unhashed.contains_hash(hash(query)));
+            assert_eq!(hashed.contains(query), packed.contains_hash(hash(query)));
+        }
+        for value in packed.iter() {
+            assert!(unhashed.contains_reduced(value));
+            assert!(packed.contains_reduced(value));
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn interpolation_lookup(
+        hashes: Vec<u64>,
+        duplicates: Vec<u64>,
+        queries: Vec<u64>,
+        p in 1u8..32,
+    ) {
+        let n = hashes.len() + duplicates.len() + 1;
+        let mut gcs = UnpackedGcs::<XxHash>::with_policy(n, p, DuplicatePolicy::Multiset);
+        for &hash in hashes.iter().chain(&duplicates).chain(duplicates.first()) {
+            gcs.insert_hash(hash).unwrap();
+        }
+
+        let mut interpolated = gcs.clone();
+        interpolated.set_lookup(Lookup::Interpolation);
+        assert_eq!(interpolated.lookup(), Lookup::Interpolation);
+        // The lookup mode does not affect equality
+        assert_eq!(interpolated, gcs);
+        for &hash in hashes.iter().chain(&duplicates) {
+            assert!(interpolated.contains_hash(hash));
+        }
+        for &hash in &queries {
+            assert_eq!(interpolated.contains_hash(hash), gcs.contains_hash(hash));
+        }
+        for value in (0..64).map(|i| (i * ((n as u64) << p)) >> 6) {
+            assert_eq!(interpolated.contains_reduced(value), gcs.contains_reduced(value));
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn storage_width_transparent(values: Vec<u64>, queries: Vec<u64>, p in 24u8..40) {
+        let n = values.len() + 1;
+        let range = (n as u64) << p;
+        let mut gcs = UnpackedGcs::<XxHash>::new(n, p);
+        assert_eq!(gcs.bits_per_value(), if range <= 1 << 32 { 32 } else { 64 });
+
+        let mut expected = Vec::new();
+        for value in values.iter().map(|value| value % range) {
+            let inserted = gcs.insert_reduced(value).unwrap();
+            assert_eq!(inserted, !expected.contains(&value));
+            expected.push(value);
+        }
+        expected.sort_unstable();
+        expected.dedup();
+
+        let packed = gcs.pack();
+        assert!(packed.iter().eq(expected.iter().cloned()));
+        assert_eq!(packed.unpack(), gcs.clone());
+        for value in queries.iter().map(|value| value % range) {
+            let contained = expected.binary_search(&value).is_ok();
+            assert_eq!(gcs.contains_reduced(value), contained);
+            assert_eq!(packed.contains_reduced(value), contained);
+        }
+    }
 }
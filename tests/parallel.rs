@@ -0,0 +1,56 @@
+#![cfg(feature = "rayon")]
+
+use {
+    golomb_set::{DuplicatePolicy, UnpackedGcs},
+    proptest::prelude::*,
+    twox_hash::XxHash,
+};
+
+proptest! {
+    #[test]
+    fn par_extend_matches_insert(items: Vec<Vec<u8>>, p in 2u8..16, multiset: bool) {
+        let policy = if multiset { DuplicatePolicy::Multiset } else { DuplicatePolicy::Set };
+
+        let mut sequential = UnpackedGcs::<XxHash>::with_policy(items.len() * 2, p, policy);
+        for item in items.iter().chain(&items) {
+            sequential.insert(item).unwrap();
+        }
+
+        let mut extended = UnpackedGcs::<XxHash>::with_policy(items.len() * 2, p, policy);
+        extended.extend(&items).unwrap();
+        extended.extend(&items).unwrap();
+
+        let mut parallel = UnpackedGcs::<XxHash>::with_policy(items.len() * 2, p, policy);
+        parallel.par_extend(&items).unwrap();
+        parallel.par_extend(&items).unwrap();
+
+        assert_eq!(sequential, extended);
+        assert_eq!(sequential, parallel);
+    }
+}
+
+#[test]
+fn par_pack_matches_pack() {
+    let items = (0u32..100_000).map(u32::to_be_bytes).collect::<Vec<_>>();
+
+    let mut gcs = UnpackedGcs::<XxHash>::new(items.len(), 10);
+    gcs.par_extend(&items).unwrap();
+
+    let (mut sequential, mut parallel) = (Vec::new(), Vec::new());
+    gcs.pack().write(&mut sequential).unwrap();
+    gcs.par_pack().write(&mut parallel).unwrap();
+
+    assert_eq!(sequential, parallel);
+    assert_eq!(gcs.pack(), gcs.par_pack());
+}
+
+#[test]
+fn par_extend_limit() {
+    let mut gcs = UnpackedGcs::<XxHash>::new(10, 10);
+    gcs.par_extend(vec![b"alpha", b"bravo"]).unwrap();
+
+    let items = (0u32..9).map(u32::to_be_bytes).collect::<Vec<_>>();
+    assert!(gcs.par_extend(&items).is_err());
+    assert!(gcs.contains(b"alpha") && gcs.contains(b"bravo"));
+    assert!(!gcs.contains(items[0]));
+}
@@ -0,0 +1,135 @@
+use {
+    golomb_set::{DuplicatePolicy, Error, Gcs, GcsPatch, UnpackedGcs},
+    proptest::prelude::*,
+    twox_hash::XxHash,
+};
+
+fn packed<I: IntoIterator<Item = u32>>(n: usize, p: u8, items: I) -> Gcs<XxHash> {
+    let mut gcs = UnpackedGcs::with_policy(n, p, DuplicatePolicy::Multiset);
+    gcs.extend(items.into_iter().map(u32::to_be_bytes)).unwrap();
+    gcs.pack()
+}
+
+fn to_vec(gcs: &Gcs<XxHash>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    gcs.write_counted(&mut buf).unwrap();
+    buf
+}
+
+proptest! {
+    #[test]
+    fn roundtrip(old: Vec<u32>, new: Vec<u32>, p in 1u8..24) {
+        let n = old.len().max(new.len()).max(1);
+        let (old, new) = (packed(n, p, old), packed(n, p, new));
+
+        let patch = old.diff(&new).unwrap();
+        assert_eq!(to_vec(&old.apply_patch(&patch).unwrap()), to_vec(&new));
+
+        let mut buf = Vec::new();
+        patch.write(&mut buf).unwrap();
+        let decoded = GcsPatch::from_reader(&mut &buf[..]).unwrap();
+        assert_eq!(decoded, patch);
+    }
+}
+
+#[test]
+fn small_change() {
+    let (n, p) = (10_000, 19);
+    let old = packed(n, p, 0..10_000);
+    let new = packed(n, p, 200..10_100);
+
+    let patch = old.diff(&new).unwrap();
+    assert_eq!(patch.removed().len(), 200);
+    assert_eq!(patch.added().len(), 100);
+    assert_eq!(to_vec(&old.apply_patch(&patch).unwrap()), to_vec(&new));
+
+    // Each changed value takes about `log2(n * 2^p / 300) + 2` bits
+    let mut buf = Vec::new();
+    patch.write(&mut buf).unwrap();
+    assert!(buf.len() < 300 * 28 / 8 + 64);
+    assert!(buf.len() * 20 < to_vec(&new).len());
+}
+
+#[test]
+fn empty() {
+    let gcs = packed(100, 8, 0..50);
+    let patch = gcs.diff(&gcs).unwrap();
+    assert!(patch.is_empty());
+    assert_eq!(to_vec(&gcs.apply_patch(&patch).unwrap()), to_vec(&gcs));
+}
+
+#[test]
+fn mismatch() {
+    let a = packed(100, 8, 0..50);
+    let b = packed(100, 8, 50..100);
+    let c = packed(100, 9, 0..50);
+
+    assert!(matches!(a.diff(&c), Err(Error::Parameters)));
+    let patch = a.diff(&b).unwrap();
+    assert!(matches!(c.apply_patch(&patch), Err(Error::Parameters)));
+    assert!(matches!(b.apply_patch(&patch), Err(Error::Patch)));
+}
+
+#[test]
+fn invalid() {
+    let patch = packed(100, 8, 0..50).diff(&packed(100, 8, 25..75)).unwrap();
+    let mut buf = Vec::new();
+    patch.write(&mut buf).unwrap();
+
+    assert!(matches!(
+        GcsPatch::from_reader(&mut &b"GCS1"[..]),
+        Err(Error::Header)
+    ));
+    assert!(GcsPatch::from_reader(&mut &buf[..buf.len() - 1]).is_err());
+
+    // A value beyond `n * 2^p`
+    let mut large = Vec::new();
+    GcsPatch::from_reader(&mut &buf[..])
+        .unwrap()
+        .write(&mut large)
+        .unwrap();
+    large[5..13].copy_from_slice(&4u64.to_be_bytes());
+    assert!(matches!(
+        GcsPatch::from_reader(&mut &large[..]),
+        Err(Error::Decode)
+    ));
+
+    // n = 0, p = 64 and n * 2^p overflowing
+    for &(n, p) in &[(0u64, 8u8), (100, 64), (1 << 40, 40)] {
+        let mut invalid = buf.clone();
+        invalid[4] = p;
+        invalid[5..13].copy_from_slice(&n.to_be_bytes());
+        assert!(matches!(
+            GcsPatch::from_reader(&mut &invalid[..]),
+            Err(Error::Parameters)
+        ));
+    }
+}
+
+#[test]
+fn malformed() {
+    // n = 1, p = 63 and one removed value with a quotient of 2, which is 2^64
+    let mut buf = b"GCSP".to_vec();
+    buf.push(63);
+    buf.extend_from_slice(&1u64.to_be_bytes());
+    buf.extend_from_slice(&1u64.to_be_bytes());
+    buf.push(63);
+    buf.extend_from_slice(&9u64.to_be_bytes());
+    buf.extend_from_slice(&[0b1100_0000, 0, 0, 0, 0, 0, 0, 0, 0]);
+    let mut added = 0u64.to_be_bytes().to_vec();
+    added.push(1);
+    added.extend_from_slice(&0u64.to_be_bytes());
+    buf.extend_from_slice(&added);
+    assert!(matches!(
+        GcsPatch::from_reader(&mut &buf[..]),
+        Err(Error::Decode)
+    ));
+
+    // A parameter larger than any chosen for the range of the patch
+    buf[4] = 8;
+    buf[5..13].copy_from_slice(&100u64.to_be_bytes());
+    assert!(matches!(
+        GcsPatch::from_reader(&mut &buf[..]),
+        Err(Error::Header)
+    ));
+}
@@ -0,0 +1,119 @@
+use {
+    golomb_set::{
+        reconcile::{Initiator, Message, Responder},
+        Error,
+    },
+    proptest::prelude::*,
+    std::collections::HashSet,
+    twox_hash::XxHash,
+};
+
+/// Runs the protocol between two in-memory peers, passing each message through
+/// its serialized form, and returns the received items and the number received
+/// in each round.
+fn reconcile(a: &[u32], b: &[u32], p: u8) -> (HashSet<u32>, Vec<usize>) {
+    let transmit = |message: Message| {
+        let mut buf = Vec::new();
+        message.write(&mut buf).unwrap();
+        Message::from_reader(&mut &buf[..]).unwrap()
+    };
+
+    let mut initiator = Initiator::<XxHash>::new(a.iter().map(|i| i.to_be_bytes()), p);
+    let mut responder = Responder::<XxHash>::new(b.iter().map(|i| i.to_be_bytes()));
+
+    let mut message = initiator.start().unwrap();
+    let mut rounds = Vec::new();
+    loop {
+        let reply = transmit(responder.receive(transmit(message)).unwrap());
+        if let Message::Items { items, .. } = &reply {
+            rounds.push(items.len());
+        }
+        match initiator.receive(reply).unwrap() {
+            Some(next) => message = next,
+            None => break,
+        }
+    }
+    assert!(initiator.is_finished());
+
+    let received = initiator
+        .into_received()
+        .into_iter()
+        .map(|item| u32::from_be_bytes([item[0], item[1], item[2], item[3]]))
+        .collect();
+    (received, rounds)
+}
+
+proptest! {
+    #[test]
+    fn missing_items(a: HashSet<u32>, b: HashSet<u32>, p in 4u8..16) {
+        let (a, b) = (a.into_iter().collect::<Vec<_>>(), b.into_iter().collect::<Vec<_>>());
+        let (received, _) = reconcile(&a, &b, p);
+
+        let a = a.into_iter().collect::<HashSet<_>>();
+        let b = b.into_iter().collect::<HashSet<_>>();
+        assert_eq!(received, b.difference(&a).cloned().collect());
+    }
+}
+
+#[test]
+fn second_round() {
+    // With `p` of 1 many of the missing items are false positives in the
+    // first sketch, which the second round finds
+    let a = (0..1000).collect::<Vec<_>>();
+    let b = (500..1500).collect::<Vec<_>>();
+    let (received, rounds) = reconcile(&a, &b, 1);
+
+    assert_eq!(rounds.len(), 2);
+    assert!(rounds[1] > 100);
+    assert_eq!(received, (1000..1500).collect());
+}
+
+#[test]
+fn single_round() {
+    let (received, rounds) = reconcile(&[1, 2, 3], &[4, 5], 8);
+
+    assert_eq!(rounds, [2]);
+    assert_eq!(received, [4, 5].iter().cloned().collect());
+}
+
+#[test]
+fn unexpected_messages() {
+    let mut initiator = Initiator::<XxHash>::new(vec!["alpha"], 8);
+    let mut responder = Responder::<XxHash>::new(vec!["beta"]);
+
+    let items = Message::Items {
+        items: Vec::new(),
+        more: false,
+    };
+    assert!(matches!(
+        initiator.receive(items.clone()),
+        Err(Error::Protocol)
+    ));
+    assert!(matches!(responder.receive(items), Err(Error::Protocol)));
+
+    let sketch = initiator.start().unwrap();
+    assert!(matches!(initiator.start(), Err(Error::Protocol)));
+    assert!(matches!(
+        initiator.receive(sketch.clone()),
+        Err(Error::Protocol)
+    ));
+
+    responder.receive(sketch.clone()).unwrap();
+    responder.receive(sketch.clone()).unwrap();
+    assert!(matches!(responder.receive(sketch), Err(Error::Protocol)));
+
+    let result = Message::from_reader(&mut &[2u8][..]);
+    assert!(matches!(result, Err(Error::Header)));
+    let result = Message::from_reader(&mut &[0u8, 0, 0, 0, 0, 0, 0, 0, 9, 1][..]);
+    assert!(matches!(result, Err(Error::Header)));
+}
+
+#[test]
+fn invalid_parameters() {
+    // p = 0, p = 64 and n * 2^p overflowing
+    let items = (0..1000u32).map(u32::to_be_bytes).collect::<Vec<_>>();
+    for &p in &[0u8, 64, 60] {
+        let mut initiator = Initiator::<XxHash>::new(&items, p);
+        assert!(matches!(initiator.start(), Err(Error::Parameters)));
+    }
+}
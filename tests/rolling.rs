@@ -0,0 +1,215 @@
+use {
+    golomb_set::{ApproximateSet, Error, RollingGcs, UnpackedGcs},
+    std::{env, fs, path::PathBuf},
+    twox_hash::XxHash,
+};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Inserts the 100 items of an interval.
+fn insert_interval(gcs: &mut RollingGcs<XxHash>, interval: u32) {
+    for i in interval * 100..(interval + 1) * 100 {
+        gcs.insert(i.to_be_bytes()).unwrap();
+    }
+}
+
+#[test]
+fn rotate_expires() {
+    let mut gcs = RollingGcs::<XxHash>::new(1000, 12, 3);
+    for interval in 0..10u32 {
+        for i in 0..100u32 {
+            assert!(gcs.insert((interval * 100 + i).to_be_bytes()).unwrap());
+        }
+
+        // Every item from the live generations is found
+        let live = interval.saturating_sub(3)..=interval;
+        for i in (live.start() * 100)..((live.end() + 1) * 100) {
+            assert!(gcs.contains(i.to_be_bytes()));
+        }
+
+        let expired = gcs.rotate();
+        assert_eq!(expired.is_some(), interval >= 3);
+        if let Some(expired) = expired {
+            let mut expected = UnpackedGcs::new(1000, 12);
+            expected
+                .extend(((interval - 3) * 100..(interval - 2) * 100).map(u32::to_be_bytes))
+                .unwrap();
+            assert_eq!(expired, expected.pack());
+        }
+        assert_eq!(gcs.packed().len(), (interval as usize + 1).min(3));
+    }
+
+    assert_eq!(gcs.rotations(), 10);
+    assert!(gcs.active().is_empty());
+    let positives = (0..600u32)
+        .filter(|i| gcs.contains(i.to_be_bytes()))
+        .count();
+    assert!(positives < 10, "{}", positives);
+}
+
+#[test]
+fn insert_refreshes() {
+    let mut gcs = RollingGcs::<XxHash>::new(10, 12, 1);
+    assert!(gcs.insert(b"alpha").unwrap());
+    gcs.rotate();
+
+    // Present in a packed generation, but kept live by inserting it again
+    assert!(!gcs.insert(b"alpha").unwrap());
+    gcs.rotate();
+    assert!(gcs.contains(b"alpha"));
+    gcs.rotate();
+    assert!(!gcs.contains(b"alpha"));
+    assert!(gcs.insert(b"alpha").unwrap());
+}
+
+#[test]
+fn insert_limit() {
+    let mut gcs = RollingGcs::<XxHash>::new(2, 20, 1);
+    gcs.insert(b"alpha").unwrap();
+    gcs.insert(b"bravo").unwrap();
+    assert!(matches!(gcs.insert(b"charlie"), Err(Error::LimitReached)));
+
+    // The limit applies to each generation
+    gcs.rotate();
+    assert!(gcs.insert(b"charlie").unwrap());
+}
+
+#[test]
+fn save_open() {
+    let dir = temp_dir("golomb-set-rolling-save-open");
+
+    let mut gcs = RollingGcs::<XxHash>::new(500, 10, 2);
+    for interval in 0..5u32 {
+        insert_interval(&mut gcs, interval);
+        gcs.save(&dir).unwrap();
+
+        // Only the live generations and the manifest are kept
+        let files = fs::read_dir(&dir).unwrap().count();
+        assert_eq!(files, gcs.packed().len() + 2);
+
+        let opened = RollingGcs::<XxHash>::open(&dir).unwrap();
+        assert_eq!(opened.n(), 500);
+        assert_eq!(opened.p(), 10);
+        assert_eq!(opened.generations(), 2);
+        assert_eq!(opened.rotations(), gcs.rotations());
+        assert_eq!(opened.active(), gcs.active());
+        assert!(opened.packed().eq(gcs.packed()));
+
+        gcs.rotate();
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn unlimited_generations() {
+    let mut gcs = RollingGcs::<XxHash>::new(10, 8, usize::MAX);
+    gcs.insert(b"alpha").unwrap();
+    for _ in 0..10 {
+        assert!(gcs.rotate().is_none());
+    }
+    assert!(gcs.contains(b"alpha"));
+}
+
+#[test]
+fn interrupted_save() {
+    let dir = temp_dir("golomb-set-rolling-interrupted-save");
+
+    let mut gcs = RollingGcs::<XxHash>::new(500, 10, 2);
+    insert_interval(&mut gcs, 0);
+    gcs.rotate();
+    insert_interval(&mut gcs, 1);
+    gcs.save(&dir).unwrap();
+
+    // Writing the new active set fails after the packed generations have
+    // been written
+    gcs.rotate();
+    insert_interval(&mut gcs, 2);
+    fs::create_dir(dir.join("2.gcs.tmp")).unwrap();
+    assert!(matches!(gcs.save(&dir), Err(Error::Io(_))));
+
+    let opened = RollingGcs::<XxHash>::open(&dir).unwrap();
+    assert_eq!(opened.rotations(), 1);
+    assert!((0..200u32).all(|i| opened.contains(i.to_be_bytes())));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn open_invalid() {
+    let dir = temp_dir("golomb-set-rolling-open-invalid");
+    assert!(matches!(
+        RollingGcs::<XxHash>::open(&dir),
+        Err(Error::Io(_))
+    ));
+
+    let mut gcs = RollingGcs::<XxHash>::new(100, 10, 2);
+    gcs.insert(b"alpha").unwrap();
+    gcs.rotate();
+    gcs.save(&dir).unwrap();
+
+    // A generation with different parameters
+    let mut other = Vec::new();
+    UnpackedGcs::<XxHash>::new(100, 11)
+        .pack()
+        .write_canonical(&mut other)
+        .unwrap();
+    fs::write(dir.join("0.gcs"), &other).unwrap();
+    assert!(matches!(
+        RollingGcs::<XxHash>::open(&dir),
+        Err(Error::Parameters)
+    ));
+
+    // Invalid parameters, and more generations than fit in a usize
+    let manifest = fs::read(dir.join("manifest")).unwrap();
+    for &(n, p) in &[(0u64, 10u8), (100, 64), (1 << 40, 40)] {
+        let mut invalid = manifest.clone();
+        invalid[4] = p;
+        invalid[5..13].copy_from_slice(&n.to_be_bytes());
+        fs::write(dir.join("manifest"), &invalid).unwrap();
+        assert!(matches!(
+            RollingGcs::<XxHash>::open(&dir),
+            Err(Error::Parameters)
+        ));
+    }
+    if usize::MAX as u64 != u64::MAX {
+        let mut invalid = manifest.clone();
+        invalid[13..21].copy_from_slice(&u64::MAX.to_be_bytes());
+        fs::write(dir.join("manifest"), &invalid).unwrap();
+        assert!(matches!(
+            RollingGcs::<XxHash>::open(&dir),
+            Err(Error::Header)
+        ));
+    }
+
+    fs::write(dir.join("manifest"), b"GCS1").unwrap();
+    assert!(matches!(
+        RollingGcs::<XxHash>::open(&dir),
+        Err(Error::Header)
+    ));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn approximate() {
+    let mut gcs = RollingGcs::<XxHash>::new(100, 8, 2);
+    insert_interval(&mut gcs, 0);
+    gcs.rotate();
+    insert_interval(&mut gcs, 1);
+
+    assert_eq!(ApproximateSet::len(&gcs), 200);
+    let mut expected = gcs.active().iter_hashes().collect::<Vec<_>>();
+    expected.extend(gcs.packed().flat_map(|gcs| gcs.iter()));
+    expected.sort_unstable();
+    assert!(gcs.iter_hashes().eq(expected));
+
+    // Roughly the sum of the rates of two full generations
+    let fpr = ApproximateSet::false_positive_rate(&gcs);
+    assert!(fpr > 1.9 / 256.0 && fpr < 2.0 / 256.0, "{}", fpr);
+}
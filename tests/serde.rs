@@ -0,0 +1,79 @@
+#![cfg(feature = "serde")]
+
+use {
+    golomb_set::{DuplicatePolicy, Gcs, UnpackedGcs},
+    proptest::prelude::*,
+    twox_hash::XxHash,
+};
+
+fn unpacked(n: usize, p: u8, policy: DuplicatePolicy, items: &[Vec<u8>]) -> UnpackedGcs<XxHash> {
+    let mut gcs = UnpackedGcs::with_policy(n, p, policy);
+    for item in items {
+        gcs.insert(item).unwrap();
+    }
+    gcs
+}
+
+proptest! {
+    #[test]
+    fn json_roundtrip(items: Vec<Vec<u8>>, p in 2u8..16, multiset: bool) {
+        let policy = if multiset { DuplicatePolicy::Multiset } else { DuplicatePolicy::Set };
+        let unpacked = unpacked(items.len() + 1, p, policy, &items);
+        let packed = unpacked.pack();
+
+        let json = serde_json::to_string(&unpacked).unwrap();
+        assert_eq!(unpacked, serde_json::from_str::<UnpackedGcs<XxHash>>(&json).unwrap());
+
+        let json = serde_json::to_string(&packed).unwrap();
+        assert_eq!(packed, serde_json::from_str::<Gcs<XxHash>>(&json).unwrap());
+    }
+
+    #[test]
+    fn bincode_roundtrip(items: Vec<Vec<u8>>, p in 2u8..16, multiset: bool) {
+        let policy = if multiset { DuplicatePolicy::Multiset } else { DuplicatePolicy::Set };
+        let unpacked = unpacked(items.len() + 1, p, policy, &items);
+        let packed = unpacked.pack();
+
+        let bytes = bincode::serialize(&unpacked).unwrap();
+        assert_eq!(unpacked, bincode::deserialize::<UnpackedGcs<XxHash>>(&bytes).unwrap());
+
+        let bytes = bincode::serialize(&packed).unwrap();
+        assert_eq!(packed, bincode::deserialize::<Gcs<XxHash>>(&bytes).unwrap());
+    }
+}
+
+#[test]
+fn invalid_packed() {
+    // The final value is missing 2 of the bits of its remainder
+    let json = r#"{"n":10,"p":8,"policy":"Multiset","len":2,"data":[0,0]}"#;
+    assert!(serde_json::from_str::<Gcs<XxHash>>(json).is_err());
+
+    // More values are encoded than given by `len`
+    let json = r#"{"n":10,"p":2,"policy":"Multiset","len":1,"data":[0,0]}"#;
+    assert!(serde_json::from_str::<Gcs<XxHash>>(json).is_err());
+
+    let json = r#"{"n":10,"p":2,"policy":"Multiset","len":5,"data":[0,0]}"#;
+    assert!(serde_json::from_str::<Gcs<XxHash>>(json).is_ok());
+}
+
+#[test]
+fn invalid_unpacked() {
+    // Unsorted
+    let json = r#"{"n":10,"p":2,"policy":"Set","values":[3,1]}"#;
+    assert!(serde_json::from_str::<UnpackedGcs<XxHash>>(json).is_err());
+
+    // Duplicates in a set
+    let json = r#"{"n":10,"p":2,"policy":"Set","values":[1,1]}"#;
+    assert!(serde_json::from_str::<UnpackedGcs<XxHash>>(json).is_err());
+
+    // Out of range
+    let json = r#"{"n":10,"p":2,"policy":"Multiset","values":[1,1,40]}"#;
+    assert!(serde_json::from_str::<UnpackedGcs<XxHash>>(json).is_err());
+
+    // Too many values
+    let json = r#"{"n":1,"p":2,"policy":"Multiset","values":[1,1]}"#;
+    assert!(serde_json::from_str::<UnpackedGcs<XxHash>>(json).is_err());
+
+    let json = r#"{"n":10,"p":2,"policy":"Multiset","values":[1,1,39]}"#;
+    assert!(serde_json::from_str::<UnpackedGcs<XxHash>>(json).is_ok());
+}
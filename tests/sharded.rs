@@ -0,0 +1,260 @@
+use {
+    digest::{
+        generic_array::{typenum::U4, GenericArray},
+        Digest,
+    },
+    golomb_set::{Error, ShardedGcs, UnpackedGcs},
+    md5::Md5,
+    std::{env, fs, path::PathBuf},
+    twox_hash::XxHash,
+};
+
+/// MD5 truncated to its first 4 bytes.
+struct Md5Short(Md5);
+
+impl Digest for Md5Short {
+    type OutputSize = U4;
+
+    fn new() -> Self {
+        Md5Short(Md5::new())
+    }
+
+    fn input<B: AsRef<[u8]>>(&mut self, data: B) {
+        self.0.input(data);
+    }
+
+    fn chain<B: AsRef<[u8]>>(self, data: B) -> Self {
+        Md5Short(self.0.chain(data))
+    }
+
+    fn result(self) -> GenericArray<u8, U4> {
+        GenericArray::clone_from_slice(&self.0.result()[..4])
+    }
+
+    fn result_reset(&mut self) -> GenericArray<u8, U4> {
+        GenericArray::clone_from_slice(&self.0.result_reset()[..4])
+    }
+
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+
+    fn output_size() -> usize {
+        4
+    }
+
+    fn digest(data: &[u8]) -> GenericArray<u8, U4> {
+        GenericArray::clone_from_slice(&Md5::digest(data)[..4])
+    }
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn items(len: u32) -> Vec<[u8; 4]> {
+    (0..len).map(u32::to_be_bytes).collect()
+}
+
+#[test]
+fn build_routes() {
+    let items = items(4000);
+    let gcs = ShardedGcs::<Md5>::build(400, 10, 16, &items).unwrap();
+    assert_eq!(gcs.shards(), 16);
+
+    for (index, shard) in (0..16).map(|index| (index, gcs.shard(index).unwrap())) {
+        // Each shard is the set of the items routed to it
+        let mut expected = UnpackedGcs::<Md5>::new(400, 10);
+        expected
+            .extend(
+                items
+                    .iter()
+                    .filter(|item| ShardedGcs::<Md5>::route(16, item) == index),
+            )
+            .unwrap();
+        assert!(shard.iter().eq(expected.pack().iter()));
+
+        // Roughly an even split
+        let len = shard.iter().count();
+        assert!(len > 200 && len < 300, "{}", len);
+    }
+
+    assert!(items.iter().all(|item| gcs.contains(item) == Some(true)));
+    // Each shard is roughly 60% full
+    let positives = (4000..8000u32)
+        .filter(|i| gcs.contains(i.to_be_bytes()).unwrap())
+        .count();
+    assert!(positives < 10, "{}", positives);
+}
+
+#[test]
+fn independent_bits() {
+    // Routing to 256 shards by a second digest of a 64 bit digest leaves the
+    // values within each shard spread across their whole range
+    let gcs = ShardedGcs::<XxHash>::build(10_000, 8, 256, items(100_000)).unwrap();
+    let range = 10_000u64 << 8;
+    for index in 0..256 {
+        let values = gcs.shard(index).unwrap().iter().collect::<Vec<_>>();
+        let low = values.iter().filter(|&&value| value < range / 2).count();
+        assert!(low * 3 > values.len() && low * 3 < values.len() * 2);
+    }
+}
+
+#[test]
+fn short_digest() {
+    // The 4 bytes of the digest are all used for the values, so items are
+    // routed by a second digest
+    let gcs = ShardedGcs::<Md5Short>::build(400, 8, 16, items(4000)).unwrap();
+    let range = 400u64 << 8;
+    for index in 0..16 {
+        let values = gcs.shard(index).unwrap().iter().collect::<Vec<_>>();
+        assert!(values.len() > 200 && values.len() < 300, "{}", values.len());
+
+        let low = values.iter().filter(|&&value| value < range / 2).count();
+        assert!(low * 3 > values.len() && low * 3 < values.len() * 2);
+    }
+}
+
+#[test]
+fn limit() {
+    assert!(matches!(
+        ShardedGcs::<Md5>::build(10, 8, 4, items(100)),
+        Err(Error::LimitReached)
+    ));
+}
+
+#[test]
+fn invalid_parameters() {
+    // n = 0, p = 0 and n * 2^p overflowing
+    for &(n, p) in &[(0usize, 8u8), (10, 0), (1 << 40, 40)] {
+        assert!(matches!(
+            ShardedGcs::<Md5>::build(n, p, 4, items(1)),
+            Err(Error::Parameters)
+        ));
+    }
+}
+
+#[test]
+#[should_panic(expected = "n and p")]
+fn new_invalid_parameters() {
+    ShardedGcs::<Md5>::new(10, 0, 4);
+}
+
+#[test]
+fn save_open_shards() {
+    let dir = temp_dir("golomb-set-sharded-save-open");
+    let items = items(5000);
+
+    let gcs = ShardedGcs::<XxHash>::build(1000, 12, 8, &items).unwrap();
+    gcs.save(&dir).unwrap();
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 9);
+
+    // Only the manifest is read
+    let opened = ShardedGcs::<XxHash>::open(&dir).unwrap();
+    assert_eq!(opened.n(), 1000);
+    assert_eq!(opened.p(), 12);
+    assert_eq!(opened.shards(), 8);
+    assert!((0..8).all(|index| opened.shard(index).is_none()));
+    assert_eq!(opened.file_name(3), gcs.file_name(3));
+
+    let mut opened = ShardedGcs::<XxHash>::open_shards(&dir, &[2, 5]).unwrap();
+    for item in &items {
+        let index = ShardedGcs::<XxHash>::route(8, item);
+        if index == 2 || index == 5 {
+            assert_eq!(opened.contains(item), Some(true));
+        } else {
+            assert_eq!(opened.contains(item), None);
+        }
+    }
+    assert_eq!(opened.shard(5), gcs.shard(5));
+
+    assert_eq!(opened.unload(5).as_ref(), gcs.shard(5));
+    assert!(opened.shard(5).is_none());
+    opened.load(&dir, 7).unwrap();
+    assert_eq!(opened.shard(7), gcs.shard(7));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn build_separately() {
+    let dir = temp_dir("golomb-set-sharded-build-separately");
+    let items = items(2000);
+
+    // Each machine builds and saves the shards it is responsible for
+    for machine in 0..2 {
+        let mut gcs = ShardedGcs::<XxHash>::new(1000, 10, 4);
+        for index in (0..4).filter(|index| index % 2 == machine) {
+            let mut shard = UnpackedGcs::<XxHash>::new(1000, 10);
+            shard
+                .extend(
+                    items
+                        .iter()
+                        .filter(|item| ShardedGcs::<XxHash>::route(4, item) == index),
+                )
+                .unwrap();
+            gcs.set_shard(index, shard.pack()).unwrap();
+        }
+        gcs.save(&dir).unwrap();
+    }
+
+    let opened = ShardedGcs::<XxHash>::open_shards(&dir, &[0, 1, 2, 3]).unwrap();
+    let expected = ShardedGcs::<XxHash>::build(1000, 10, 4, &items).unwrap();
+    for index in 0..4 {
+        assert_eq!(opened.shard(index), expected.shard(index));
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn invalid() {
+    let dir = temp_dir("golomb-set-sharded-invalid");
+
+    let mut gcs = ShardedGcs::<Md5>::new(100, 10, 2);
+    assert!(matches!(
+        gcs.set_shard(0, UnpackedGcs::new(100, 11).pack()),
+        Err(Error::Parameters)
+    ));
+    gcs.set_shard(0, UnpackedGcs::new(100, 10).pack()).unwrap();
+    gcs.save(&dir).unwrap();
+
+    // The other shard was never saved
+    let mut opened = ShardedGcs::<Md5>::open(&dir).unwrap();
+    assert!(matches!(opened.load(&dir, 1), Err(Error::Io(_))));
+
+    // A shard with different parameters
+    let mut other = Vec::new();
+    UnpackedGcs::<Md5>::new(50, 10)
+        .pack()
+        .write_canonical(&mut other)
+        .unwrap();
+    fs::write(dir.join(opened.file_name(1)), &other).unwrap();
+    assert!(matches!(opened.load(&dir, 1), Err(Error::Parameters)));
+
+    // Invalid parameters: n = 0, p = 64 and n * 2^p overflowing
+    let manifest = fs::read(dir.join("manifest")).unwrap();
+    for &(n, p) in &[(0u64, 10u8), (100, 64), (1 << 40, 40)] {
+        let mut invalid = manifest.clone();
+        invalid[4] = p;
+        invalid[5..13].copy_from_slice(&n.to_be_bytes());
+        fs::write(dir.join("manifest"), &invalid).unwrap();
+        assert!(matches!(
+            ShardedGcs::<Md5>::open(&dir),
+            Err(Error::Parameters)
+        ));
+    }
+    fs::write(dir.join("manifest"), &manifest).unwrap();
+
+    // Shard files outside of the directory
+    let mut manifest = fs::read(dir.join("manifest")).unwrap();
+    let len = manifest.len();
+    manifest[len - 5..].copy_from_slice(b"../1x");
+    fs::write(dir.join("manifest"), &manifest).unwrap();
+    assert!(matches!(ShardedGcs::<Md5>::open(&dir), Err(Error::Header)));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,79 @@
+use {
+    golomb_set::{Error, Gcs, UnpackedGcs},
+    proptest::prelude::*,
+    twox_hash::XxHash,
+};
+
+fn packed(n: usize, p: u8, items: &[Vec<u8>]) -> Gcs<XxHash> {
+    let mut gcs = UnpackedGcs::new(n, p);
+    for item in items {
+        gcs.insert(item).unwrap();
+    }
+    gcs.pack()
+}
+
+proptest! {
+    #[test]
+    fn hex_roundtrip(items: Vec<Vec<u8>>, p in 1u8..32) {
+        let gcs = packed(items.len() + 1, p, &items);
+        let hex = gcs.to_hex();
+
+        assert_eq!(gcs, Gcs::from_hex(&hex, items.len() + 1, p).unwrap());
+        assert_eq!(gcs, Gcs::from_hex(hex.to_uppercase(), items.len() + 1, p).unwrap());
+    }
+
+    #[test]
+    fn base64_roundtrip(items: Vec<Vec<u8>>, p in 1u8..32) {
+        let gcs = packed(items.len() + 1, p, &items);
+
+        assert_eq!(gcs, Gcs::from_base64(gcs.to_base64(), items.len() + 1, p).unwrap());
+    }
+
+    #[test]
+    fn display_roundtrip(items: Vec<Vec<u8>>, p in 1u8..32) {
+        let gcs = packed(items.len() + 1, p, &items);
+
+        assert_eq!(gcs, gcs.to_string().parse().unwrap());
+    }
+}
+
+#[test]
+fn text_encodings() {
+    let gcs = packed(10, 8, &[b"alpha".to_vec(), b"beta".to_vec()]);
+    let mut counted = Vec::new();
+    gcs.write_counted(&mut counted).unwrap();
+
+    assert_eq!(gcs.to_hex().len(), counted.len() * 2);
+    assert_eq!(gcs.to_base64().len(), counted.len().div_ceil(3) * 4);
+    // Magic, p, n and the number of values in the canonical encoding
+    assert!(gcs.to_string().starts_with("R0NTMQgAAAAAAAAACgAAAAAAAAAC"));
+}
+
+#[test]
+fn invalid_text() {
+    let result = Gcs::<XxHash>::from_hex("0g", 10, 8);
+    assert!(matches!(result, Err(Error::Hex)));
+    let result = Gcs::<XxHash>::from_hex("abc", 10, 8);
+    assert!(matches!(result, Err(Error::Hex)));
+
+    let result = Gcs::<XxHash>::from_base64("AB!=", 10, 8);
+    assert!(matches!(result, Err(Error::Base64)));
+    let result = "R0NT!Q==".parse::<Gcs<XxHash>>();
+    assert!(matches!(result, Err(Error::Base64)));
+
+    // Valid text, but without the number of values
+    let result = Gcs::<XxHash>::from_hex("ff", 10, 8);
+    assert!(matches!(result, Err(Error::Header)));
+    // Valid text, but an incomplete Golomb-Rice encoding
+    let result = Gcs::<XxHash>::from_hex("0000000000000001ff", 10, 8);
+    assert!(matches!(result, Err(Error::Decode)));
+    // Valid text, but fewer values than given in the header
+    let result = Gcs::<XxHash>::from_hex("000000000000000200", 10, 7);
+    assert!(matches!(result, Err(Error::Decode)));
+    // Valid text, but invalid parameters
+    let result = Gcs::<XxHash>::from_hex("0000000000000000", 0, 8);
+    assert!(matches!(result, Err(Error::Parameters)));
+    // Valid base64, but without the canonical header
+    let result = "AAAA".parse::<Gcs<XxHash>>();
+    assert!(result.is_err());
+}